@@ -0,0 +1,80 @@
+//! Times `SegmentList` on a region fragmented into tens of thousands of
+//! holes. Run with `cargo bench`.
+
+use std::time::Instant;
+
+use so_project::structures::segment_list::{AllocationPolicy, Segment, SegmentList};
+
+const SEGMENTS: usize = 50_000;
+
+/// Deterministic segment lengths between 1 and 16 blocks.
+fn lengths(count: usize) -> Vec<usize> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    (0..count)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % 16) as usize + 1
+        })
+        .collect()
+}
+
+fn bench(policy: AllocationPolicy) {
+    let lengths = lengths(SEGMENTS);
+    let capacity = lengths.iter().sum::<usize>() * 2;
+    let mut segment_list = SegmentList::with_policy(
+        vec![Segment {
+            offset: 0,
+            length: capacity,
+        }],
+        policy,
+    );
+
+    let start = Instant::now();
+    let mut allocated = lengths
+        .iter()
+        .map(|length| segment_list.alloc(*length).unwrap())
+        .collect::<Vec<_>>();
+    let fill = start.elapsed();
+
+    // Freeing every other segment leaves about `SEGMENTS / 2` holes.
+    let start = Instant::now();
+    let mut index = 0;
+    allocated.retain(|segment| {
+        index += 1;
+        if index % 2 == 0 {
+            segment_list.free(segment.clone());
+        }
+        index % 2 != 0
+    });
+    let fragment = start.elapsed();
+
+    let start = Instant::now();
+    for length in lengths.iter().rev() {
+        if let Some(segment) = segment_list.alloc(*length) {
+            segment_list.free(segment);
+        }
+    }
+    let churn = start.elapsed();
+
+    println!(
+        "{:<10} {:>6} holes  fill {:>9.2?}  fragment {:>9.2?}  alloc+free {:>9.2?}",
+        format!("{:?}", policy),
+        segment_list.holes(),
+        fill,
+        fragment,
+        churn
+    );
+}
+
+fn main() {
+    for policy in [
+        AllocationPolicy::FirstFit,
+        AllocationPolicy::NextFit,
+        AllocationPolicy::BestFit,
+        AllocationPolicy::WorstFit,
+    ] {
+        bench(policy);
+    }
+}
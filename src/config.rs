@@ -0,0 +1,453 @@
+use std::fmt;
+
+use crate::memory::MemoryBackend;
+use crate::paging::ReplacementPolicy;
+use crate::resources::{DeadlockRecovery, VictimSelection};
+use crate::scheduler::{AgingPolicy, Scheduler, SchedulerKind};
+use crate::structures::fixed_partitions::FixedQueueing;
+use crate::structures::segment_list::AllocationPolicy;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct MemoryConfig {
+    pub real_time_partition_size: usize,
+    pub user_partition_size: usize,
+    pub backend: MemoryBackend,
+    /// Placement policy of the `Segments` backend.
+    pub allocation: AllocationPolicy,
+    /// Blocks per page and per frame with the `Paged` backend.
+    pub page_size: usize,
+    /// Frames given to each process with the `Paged` backend.
+    pub frames_per_process: usize,
+    pub replacement: ReplacementPolicy,
+    /// Compact a `Segments` partition when a process does not fit in any
+    /// hole but fits in its free blocks.
+    pub compaction: bool,
+    /// Blocks compaction moves per tick, to derive its cost.
+    pub compaction_blocks_per_tick: usize,
+    /// Partition sizes of the real-time region with the `Fixed` backend.
+    pub real_time_fixed_partitions: Vec<usize>,
+    /// Partition sizes of the user region with the `Fixed` backend.
+    pub user_fixed_partitions: Vec<usize>,
+    pub fixed_queueing: FixedQueueing,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        MemoryConfig {
+            real_time_partition_size: 64,
+            user_partition_size: 960,
+            backend: MemoryBackend::Segments,
+            allocation: AllocationPolicy::FirstFit,
+            page_size: 16,
+            frames_per_process: 4,
+            replacement: ReplacementPolicy::Fifo,
+            compaction: false,
+            compaction_blocks_per_tick: 32,
+            real_time_fixed_partitions: vec![16, 16, 32],
+            user_fixed_partitions: vec![64, 128, 256, 512],
+            fixed_queueing: FixedQueueing::Shared,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct DiskConfig {
+    pub allocation: AllocationPolicy,
+}
+
+/// Number of units of each resource class.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResourceConfig {
+    pub scanners: usize,
+    pub printers: usize,
+    pub modems: usize,
+    pub sata_devices: usize,
+    pub deadlock_recovery: DeadlockRecovery,
+    pub deadlock_victim: VictimSelection,
+    /// Only grant requests that leave the system in a safe state (Banker's
+    /// algorithm), blocking the others.
+    pub deadlock_avoidance: bool,
+}
+
+impl Default for ResourceConfig {
+    fn default() -> Self {
+        ResourceConfig {
+            scanners: 1,
+            printers: 2,
+            modems: 1,
+            sata_devices: 2,
+            deadlock_recovery: DeadlockRecovery::None,
+            deadlock_victim: VictimSelection::LowestPriority,
+            deadlock_avoidance: false,
+        }
+    }
+}
+
+/// Moving blocked processes out of memory to admit waiting arrivals.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SwapConfig {
+    pub enabled: bool,
+    /// Ticks between choosing a victim and its memory being freed.
+    pub swap_out_ticks: usize,
+    /// Ticks between an unblocked process getting memory back and it being ready.
+    pub swap_in_ticks: usize,
+}
+
+impl Default for SwapConfig {
+    fn default() -> Self {
+        SwapConfig {
+            enabled: false,
+            swap_out_ticks: 2,
+            swap_in_ticks: 2,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SchedulerConfig {
+    pub policy: SchedulerKind,
+    /// Number of user priority levels, served by one feedback queue each.
+    pub user_queues: usize,
+    /// Quantum of each user queue; the last one is repeated for the remaining queues.
+    pub quanta: Vec<usize>,
+    pub aging: AgingPolicy,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            policy: SchedulerKind::FeedbackQueues,
+            user_queues: 3,
+            quanta: vec![1],
+            aging: AgingPolicy::default(),
+        }
+    }
+}
+
+impl SchedulerConfig {
+    /// The quantum of every user queue, in queue order.
+    pub fn level_quanta(&self) -> Vec<usize> {
+        let last = *self.quanta.last().unwrap_or(&1);
+        (0..self.user_queues)
+            .map(|level| *self.quanta.get(level).unwrap_or(&last))
+            .collect()
+    }
+
+    pub fn build(&self) -> Box<dyn Scheduler> {
+        self.policy.build(&self.level_quanta(), self.aging)
+    }
+}
+
+/// Every tunable of the simulation. Missing keys keep the built-in defaults.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct Config {
+    pub memory: MemoryConfig,
+    pub disk: DiskConfig,
+    pub resources: ResourceConfig,
+    pub swap: SwapConfig,
+    pub scheduler: SchedulerConfig,
+    /// Check the free lists after every memory and disk operation, panicking
+    /// on the first broken invariant.
+    pub paranoid: bool,
+    /// Log the details behind decisions, such as the safe sequence of every
+    /// grant with deadlock avoidance.
+    pub verbose: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    /// 1-based line of the offending entry, `0` when it is not tied to a line.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "{}", self.message),
+            line => write!(f, "line {}: {}", line, self.message),
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Config, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|error| ConfigError {
+            line: 0,
+            message: format!("could not read {}: {}", path, error),
+        })?;
+        Config::parse(&text)
+    }
+
+    /// Parses a TOML-like file made of `[section]` headers and `key = value`
+    /// lines, where values are numbers, booleans, strings or lists of numbers.
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let mut config = Config::default();
+        let mut section = String::new();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: String| ConfigError {
+                line: line_number,
+                message,
+            };
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+            {
+                section = name.trim().to_string();
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error(format!("expected `key = value`, found `{}`", line)))?;
+            let (key, value) = (key.trim(), value.trim().trim_matches('"'));
+            config.set(&section, key, value).map_err(error)?;
+        }
+        config
+            .check()
+            .map_err(|message| ConfigError { line: 0, message })?;
+        Ok(config)
+    }
+
+    fn set(&mut self, section: &str, key: &str, value: &str) -> Result<(), String> {
+        match (section, key) {
+            ("memory", "real_time_partition_size") => {
+                self.memory.real_time_partition_size = parse_number(key, value)?
+            }
+            ("memory", "user_partition_size") => {
+                self.memory.user_partition_size = parse_number(key, value)?
+            }
+            ("memory", "backend") => self.memory.backend = value.parse()?,
+            ("memory", "allocation") => self.memory.allocation = value.parse()?,
+            ("memory", "page_size") => self.memory.page_size = parse_number(key, value)?,
+            ("memory", "frames_per_process") => {
+                self.memory.frames_per_process = parse_number(key, value)?
+            }
+            ("memory", "replacement") => self.memory.replacement = value.parse()?,
+            ("memory", "compaction") => self.memory.compaction = parse_bool(key, value)?,
+            ("memory", "compaction_blocks_per_tick") => {
+                self.memory.compaction_blocks_per_tick = parse_number(key, value)?
+            }
+            ("memory", "real_time_fixed_partitions") => {
+                self.memory.real_time_fixed_partitions = parse_list(key, value)?
+            }
+            ("memory", "user_fixed_partitions") => {
+                self.memory.user_fixed_partitions = parse_list(key, value)?
+            }
+            ("memory", "fixed_queueing") => self.memory.fixed_queueing = value.parse()?,
+            ("disk", "allocation") => self.disk.allocation = value.parse()?,
+            ("resources", "scanners") => self.resources.scanners = parse_number(key, value)?,
+            ("resources", "printers") => self.resources.printers = parse_number(key, value)?,
+            ("resources", "modems") => self.resources.modems = parse_number(key, value)?,
+            ("resources", "sata_devices") => {
+                self.resources.sata_devices = parse_number(key, value)?
+            }
+            ("resources", "deadlock_recovery") => {
+                self.resources.deadlock_recovery = value.parse()?
+            }
+            ("resources", "deadlock_victim") => self.resources.deadlock_victim = value.parse()?,
+            ("resources", "deadlock_avoidance") => {
+                self.resources.deadlock_avoidance = parse_bool(key, value)?
+            }
+            ("swap", "enabled") => self.swap.enabled = parse_bool(key, value)?,
+            ("swap", "swap_out_ticks") => self.swap.swap_out_ticks = parse_number(key, value)?,
+            ("swap", "swap_in_ticks") => self.swap.swap_in_ticks = parse_number(key, value)?,
+            ("scheduler", "policy") => self.scheduler.policy = value.parse()?,
+            ("scheduler", "user_queues") => self.scheduler.user_queues = parse_number(key, value)?,
+            ("scheduler", "quanta") => self.scheduler.quanta = parse_list(key, value)?,
+            ("scheduler", "aging_threshold") => {
+                self.scheduler.aging.threshold = parse_optional_ticks(key, value)?
+            }
+            ("scheduler", "boost_interval") => {
+                self.scheduler.aging.boost_interval = parse_optional_ticks(key, value)?
+            }
+            _ if section.is_empty() => return Err(format!("unknown key `{}`", key)),
+            _ => return Err(format!("unknown key `{}` in [{}]", key, section)),
+        }
+        Ok(())
+    }
+
+    /// Rejects values that would make a manager unusable.
+    pub fn check(&self) -> Result<(), String> {
+        if self.memory.page_size == 0 || self.memory.frames_per_process == 0 {
+            return Err(String::from(
+                "`page_size` and `frames_per_process` must be at least 1",
+            ));
+        }
+        if self.memory.backend == MemoryBackend::Fixed {
+            for (name, sizes, region) in [
+                (
+                    "real_time_fixed_partitions",
+                    &self.memory.real_time_fixed_partitions,
+                    self.memory.real_time_partition_size,
+                ),
+                (
+                    "user_fixed_partitions",
+                    &self.memory.user_fixed_partitions,
+                    self.memory.user_partition_size,
+                ),
+            ] {
+                if sizes.contains(&0) {
+                    return Err(format!("`{}` must hold positive sizes", name));
+                }
+                if sizes.iter().sum::<usize>() > region {
+                    return Err(format!(
+                        "`{}` add up to {} blocks, more than the {} of their region",
+                        name,
+                        sizes.iter().sum::<usize>(),
+                        region
+                    ));
+                }
+            }
+        }
+        if self.memory.compaction_blocks_per_tick == 0 {
+            return Err(String::from(
+                "`compaction_blocks_per_tick` must be at least 1",
+            ));
+        }
+        if self.swap.swap_out_ticks == 0 || self.swap.swap_in_ticks == 0 {
+            return Err(String::from(
+                "`swap_out_ticks` and `swap_in_ticks` must be at least 1",
+            ));
+        }
+        if self.scheduler.user_queues == 0 {
+            return Err(String::from("`user_queues` must be at least 1"));
+        }
+        if self.scheduler.quanta.is_empty() || self.scheduler.quanta.contains(&0) {
+            return Err(String::from("`quanta` must hold positive ticks"));
+        }
+        if self.scheduler.quanta.len() > self.scheduler.user_queues {
+            return Err(format!(
+                "{} quanta given for {} user queues",
+                self.scheduler.quanta.len(),
+                self.scheduler.user_queues
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(key: &str, value: &str) -> Result<usize, String> {
+    value.parse::<usize>().map_err(|_| {
+        format!(
+            "`{}` expects a non-negative integer, found `{}`",
+            key, value
+        )
+    })
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    value
+        .parse::<bool>()
+        .map_err(|_| format!("`{}` expects true or false, found `{}`", key, value))
+}
+
+/// `0` disables the setting.
+fn parse_optional_ticks(key: &str, value: &str) -> Result<Option<usize>, String> {
+    let ticks = parse_number(key, value)?;
+    Ok(Some(ticks).filter(|ticks| *ticks > 0))
+}
+
+fn parse_list(key: &str, value: &str) -> Result<Vec<usize>, String> {
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|item| parse_number(key, item.trim()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_file_keeps_defaults() {
+        assert_eq!(Config::parse("# nothing\n\n"), Ok(Config::default()));
+    }
+
+    #[test]
+    fn parses_every_section() {
+        let config = Config::parse(
+            "[memory]\n\
+             real_time_partition_size = 32 # comment\n\
+             user_partition_size = 128\n\
+             allocation = \"best-fit\"\n\
+             [disk]\n\
+             allocation = \"next-fit\"\n\
+             [resources]\n\
+             printers = 3\n\
+             [swap]\n\
+             enabled = true\n\
+             swap_in_ticks = 5\n\
+             [scheduler]\n\
+             policy = \"rr\"\n\
+             user_queues = 4\n\
+             quanta = [1, 2, 4]\n\
+             aging_threshold = 10\n",
+        )
+        .unwrap();
+        assert_eq!(config.memory.real_time_partition_size, 32);
+        assert_eq!(config.memory.user_partition_size, 128);
+        assert_eq!(config.memory.allocation, AllocationPolicy::BestFit);
+        assert_eq!(config.disk.allocation, AllocationPolicy::NextFit);
+        assert_eq!(config.resources.printers, 3);
+        assert_eq!(config.resources.scanners, 1);
+        assert!(config.swap.enabled);
+        assert_eq!(config.swap.swap_out_ticks, 2);
+        assert_eq!(config.swap.swap_in_ticks, 5);
+        assert_eq!(config.scheduler.policy, SchedulerKind::RoundRobin);
+        assert_eq!(config.scheduler.level_quanta(), vec![1, 2, 4, 4]);
+        assert_eq!(config.scheduler.aging.threshold, Some(10));
+        assert_eq!(config.scheduler.aging.boost_interval, None);
+    }
+
+    #[test]
+    fn reports_line_of_bad_values() {
+        let error = Config::parse("[memory]\nuser_partition_size = lots\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        let error = Config::parse("[memory]\nswap = 1\n").unwrap_err();
+        assert_eq!(error.message, "unknown key `swap` in [memory]");
+    }
+
+    #[test]
+    fn parses_deadlock_recovery() {
+        let config = Config::parse(
+            "[resources]\ndeadlock_recovery = \"abort-one\"\ndeadlock_victim = \"least-cpu\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.resources.deadlock_recovery,
+            DeadlockRecovery::AbortOne
+        );
+        assert_eq!(config.resources.deadlock_victim, VictimSelection::LeastCpu);
+        assert!(!config.resources.deadlock_avoidance);
+        let error = Config::parse("[resources]\ndeadlock_recovery = \"retry\"\n").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn rejects_more_quanta_than_queues() {
+        let result = Config::parse("[scheduler]\nuser_queues = 2\nquanta = 1, 2, 3\n");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn rejects_fixed_partitions_larger_than_their_region() {
+        let error = Config::parse(
+            "[memory]\nbackend = \"fixed\"\nreal_time_partition_size = 32\n\
+             user_fixed_partitions = [100, 100]\nfixed_queueing = \"per-partition\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.message,
+            "`real_time_fixed_partitions` add up to 64 blocks, more than the 32 of their region"
+        );
+        let config = Config::parse("[memory]\nuser_fixed_partitions = [100, 100]\n").unwrap();
+        assert_eq!(config.memory.user_fixed_partitions, vec![100, 100]);
+    }
+}
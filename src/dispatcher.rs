@@ -1,7 +1,11 @@
 use crate::{
-    memory::{AllocMemoryError, MemoryManager},
-    parsers::{files_parser::DiskOperationDefinition, processes_parser::ProcessDefinition},
-    process::{DiskOperation, Process},
+    events::SimEvent,
+    memory::{AllocMemoryError, MemoryManager, MemoryRequest},
+    parsers::{
+        files_parser::DiskOperationDefinition,
+        processes_parser::{MemoryOperationDefinition, ProcessDefinition},
+    },
+    process::{DiskOperation, MemoryOperation, Process, ResourceRequest},
 };
 
 pub struct Dispatcher {
@@ -22,31 +26,56 @@ impl Dispatcher {
 
     pub fn generate_new_processes(
         &mut self,
-        mut memory_manager: &mut MemoryManager,
+        memory_manager: &mut MemoryManager,
         timestamp: usize,
+        events: &mut Vec<SimEvent>,
     ) -> Vec<Process> {
         let mut new_processes = Vec::new();
+        // Fixed partition queues whose head is still waiting: the processes
+        // behind it wait too.
+        let mut waiting_queues = Vec::new();
         let mut index = 0;
         while index < self.processes_definitions.len() {
             if self.processes_definitions[index].init_time <= timestamp {
-                match self.build_process(&self.processes_definitions[index], &mut memory_manager) {
+                let process_definition = &self.processes_definitions[index];
+                let queue = memory_manager.queue_of(
+                    process_definition.priority,
+                    process_definition.num_memory_blocks,
+                );
+                let result = match queue {
+                    Some(queue) if waiting_queues.contains(&queue) => {
+                        Err(AllocMemoryError::Unavailable)
+                    }
+                    _ => self.build_process(process_definition, memory_manager),
+                };
+                match result {
                     Err(AllocMemoryError::Unavailable) => {
-                        println!(
-                            "Unavailable Memory for process {}. Waiting memory for creation\n",
-                            self.processes_definitions[index].id
-                        );
+                        events.push(SimEvent::MemoryUnavailable {
+                            pid: process_definition.id,
+                            priority: process_definition.priority,
+                            blocks: process_definition.num_memory_blocks,
+                        });
+                        waiting_queues.extend(queue);
                         index += 1;
                     }
                     Err(AllocMemoryError::Unsupported) => {
-                        println!(
-                            "Unsupported Memory for process {}. Skipping process\n",
-                            self.processes_definitions[index].id
-                        );
+                        let process_definition = &self.processes_definitions[index];
+                        events.push(SimEvent::MemoryUnsupported {
+                            pid: process_definition.id,
+                            priority: process_definition.priority,
+                            blocks: process_definition.num_memory_blocks,
+                        });
                         self.processes_definitions.remove(index);
                     }
                     Ok(process) => {
-                        println!("Created process");
-                        process.println();
+                        events.push(SimEvent::ProcessCreated {
+                            pid: process.software_context.id,
+                            priority: process.software_context.priority,
+                            arrival_time: self.processes_definitions[index].init_time,
+                            cpu_time: process.software_context.cpu_time,
+                            address_space: process.address_space.clone(),
+                            resources: process.requested_resources(),
+                        });
                         new_processes.push(process);
                         self.processes_definitions.remove(index);
                     }
@@ -86,11 +115,43 @@ impl Dispatcher {
                 },
             )
             .collect();
-        let address_space = memory_manager.alloc(
-            process_definition.priority,
-            process_definition.num_memory_blocks,
-        )?;
-        let new_process = Process::new(
+        let process_memory_ops = process_definition
+            .memory_operations
+            .iter()
+            .map(
+                |memory_operation_definition| match memory_operation_definition {
+                    MemoryOperationDefinition::Access { page } => {
+                        MemoryOperation::Access { page: *page }
+                    }
+                    MemoryOperationDefinition::SegmentAccess { segment, offset } => {
+                        MemoryOperation::SegmentAccess {
+                            segment: *segment,
+                            offset: *offset,
+                        }
+                    }
+                    MemoryOperationDefinition::Grow { blocks } => {
+                        MemoryOperation::Grow { blocks: *blocks }
+                    }
+                    MemoryOperationDefinition::Shrink { blocks } => {
+                        MemoryOperation::Shrink { blocks: *blocks }
+                    }
+                },
+            )
+            .collect();
+        let process_resource_requests = process_definition
+            .resource_requests
+            .iter()
+            .map(|request| ResourceRequest {
+                resource: request.resource,
+                after: request.after,
+            })
+            .collect();
+        let request = match process_definition.segments {
+            Some(segments) => MemoryRequest::Segments(segments),
+            None => MemoryRequest::Blocks(process_definition.num_memory_blocks),
+        };
+        let address_space = memory_manager.alloc_request(process_definition.priority, &request)?;
+        let mut new_process = Process::new(
             process_definition.id,
             process_definition.priority,
             process_definition.cpu_time,
@@ -99,14 +160,49 @@ impl Dispatcher {
             process_definition.use_modem,
             process_definition.use_sata,
             process_disk_ops,
+            process_memory_ops,
+            process_resource_requests,
             address_space,
         );
+        // A claim below what the process requests is raised to it.
+        let mut max_claims = new_process.requested_units();
+        for (resource, units) in &process_definition.max_claims {
+            let claim = &mut max_claims[*resource as usize];
+            *claim = (*claim).max(*units);
+        }
+        new_process.software_context.max_claims = max_claims;
         Ok(new_process)
     }
 
-    pub fn has_more_processes(&self, timestamp: usize) -> bool {
+    /// Priority and blocks of the first arrived process still waiting for memory.
+    pub fn waiting_for_memory(&self, timestamp: usize) -> Option<(usize, usize)> {
+        self.processes_definitions
+            .iter()
+            .find(|process_definition| process_definition.init_time <= timestamp)
+            .map(|process_definition| {
+                (
+                    process_definition.priority,
+                    process_definition.num_memory_blocks,
+                )
+            })
+    }
+
+    /// Whether a process has still to arrive or is waiting for memory.
+    pub fn has_more_processes(&self) -> bool {
+        !self.processes_definitions.is_empty()
+    }
+
+    /// Whether a process arrives at `timestamp` or later.
+    pub fn has_arrivals_from(&self, timestamp: usize) -> bool {
         self.processes_definitions
             .iter()
             .any(|process_definition| process_definition.init_time >= timestamp)
     }
+
+    /// Processes that have still to arrive or are waiting for memory.
+    pub fn pending_pids(&self) -> impl Iterator<Item = usize> + '_ {
+        self.processes_definitions
+            .iter()
+            .map(|process_definition| process_definition.id)
+    }
 }
@@ -0,0 +1,521 @@
+mod jsonl;
+
+use crate::memory::AddressSpace;
+use crate::resources::Resource;
+use crate::segmentation::SegmentKind;
+use crate::structures::segment_list::{FreeSpace, Segment};
+
+pub use self::jsonl::JsonlRenderer;
+
+/// Everything observable that happens during a simulation run.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SimEvent {
+    FileSystemLoaded {
+        num_blocks: usize,
+        files: Vec<(char, Segment)>,
+    },
+    ProcessCreated {
+        pid: usize,
+        priority: usize,
+        arrival_time: usize,
+        cpu_time: usize,
+        address_space: AddressSpace,
+        resources: Vec<Resource>,
+    },
+    MemoryUnavailable {
+        pid: usize,
+        priority: usize,
+        blocks: usize,
+    },
+    MemoryUnsupported {
+        pid: usize,
+        priority: usize,
+        blocks: usize,
+    },
+    Enqueued {
+        pid: usize,
+        priority: usize,
+    },
+    Dispatched {
+        pid: usize,
+        priority: usize,
+    },
+    Preempted {
+        pid: usize,
+        priority: usize,
+    },
+    /// A waiting process moved up from queue `from` to queue `to`.
+    Promoted {
+        pid: usize,
+        from: usize,
+        to: usize,
+    },
+    /// Every waiting user process is about to be moved to the first queue.
+    PriorityBoost,
+    /// The process held the CPU during this tick.
+    Executed {
+        pid: usize,
+        priority: usize,
+    },
+    /// No process held the CPU during this tick.
+    Idle,
+    CpuInstruction {
+        pid: usize,
+        priority: usize,
+    },
+    Blocked {
+        pid: usize,
+        priority: usize,
+        resource: Resource,
+    },
+    ResourceGranted {
+        pid: usize,
+        priority: usize,
+        resource: Resource,
+    },
+    ResourceReleased {
+        pid: usize,
+        resource: Resource,
+    },
+    Unblocked {
+        pid: usize,
+        priority: usize,
+        resource: Resource,
+    },
+    FileCreated {
+        pid: usize,
+        file_name: char,
+        segment: Segment,
+    },
+    FileCreateFailed {
+        pid: usize,
+        file_name: char,
+        blocks: usize,
+    },
+    FileDeleted {
+        pid: usize,
+        file_name: char,
+        segment: Segment,
+    },
+    FileDeleteDenied {
+        pid: usize,
+        file_name: char,
+    },
+    FileNotFound {
+        pid: usize,
+        file_name: char,
+    },
+    /// The page was already in a frame.
+    PageHit {
+        pid: usize,
+        page: usize,
+    },
+    /// The page was loaded in the frame at `frame`, replacing `evicted`.
+    PageFault {
+        pid: usize,
+        page: usize,
+        frame: usize,
+        evicted: Option<usize>,
+    },
+    /// `offset` of a segment was translated to the physical block `address`.
+    SegmentAccessed {
+        pid: usize,
+        segment: SegmentKind,
+        offset: usize,
+        address: usize,
+    },
+    /// An access past the `limit` of a segment; the process is terminated.
+    ProtectionFault {
+        pid: usize,
+        priority: usize,
+        segment: SegmentKind,
+        offset: usize,
+        limit: usize,
+    },
+    /// The memory of a process, its data segment when segmented, went from
+    /// `from` to `to`; a different offset means it was moved.
+    MemoryResized {
+        pid: usize,
+        priority: usize,
+        from: Segment,
+        to: Segment,
+    },
+    /// The process waits until its memory can grow to `blocks`.
+    BlockedOnMemory {
+        pid: usize,
+        priority: usize,
+        blocks: usize,
+    },
+    /// The memory of the process can never be given `blocks`; it goes on
+    /// with the memory it has.
+    MemoryResizeDenied {
+        pid: usize,
+        priority: usize,
+        blocks: usize,
+    },
+    /// The processes blocked on resources in `pids` wait for each other and
+    /// can never be unblocked.
+    Deadlocked {
+        pids: Vec<usize>,
+        resources: Vec<Resource>,
+    },
+    /// `pid` got a unit of `resource` with deadlock avoidance, every process
+    /// still being able to terminate in the order of `sequence`.
+    SafeSequence {
+        pid: usize,
+        resource: Resource,
+        sequence: Vec<usize>,
+    },
+    /// `pid` requested a unit of `resource` beyond its claim, with deadlock
+    /// avoidance; the request is denied.
+    ClaimExceeded {
+        pid: usize,
+        priority: usize,
+        resource: Resource,
+    },
+    /// The run stops with the processes in `pids` left: blocked on resources
+    /// or waiting for memory that nothing will ever free.
+    Stranded {
+        pids: Vec<usize>,
+    },
+    /// A deadlocked process is terminated, without completing, to break the
+    /// deadlock; its memory is freed.
+    Aborted {
+        pid: usize,
+        priority: usize,
+        address_space: AddressSpace,
+    },
+    /// A deadlocked process gave back its resources and will request them
+    /// again.
+    RolledBack {
+        pid: usize,
+        priority: usize,
+    },
+    /// The memory of a blocked process was written to the swap area and freed.
+    SwappedOut {
+        pid: usize,
+        priority: usize,
+        blocks: usize,
+    },
+    /// An unblocked process was read back from the swap area and is ready.
+    SwappedIn {
+        pid: usize,
+        priority: usize,
+        address_space: AddressSpace,
+    },
+    Terminated {
+        pid: usize,
+        priority: usize,
+        address_space: AddressSpace,
+    },
+    /// The processes of a memory partition were slid together, merging its
+    /// holes, in what would take `cost` ticks.
+    Compacted {
+        area: StorageArea,
+        moved_blocks: usize,
+        relocated: usize,
+        cost: usize,
+    },
+    /// Free space of a memory partition or of the disk at the end of a tick.
+    FreeSpaceSampled {
+        area: StorageArea,
+        space: FreeSpace,
+    },
+}
+
+/// A region managed by its own free list.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum StorageArea {
+    RealTimeMemory,
+    UserMemory,
+    Disk,
+}
+
+impl StorageArea {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageArea::RealTimeMemory => "real_time_memory",
+            StorageArea::UserMemory => "user_memory",
+            StorageArea::Disk => "disk",
+        }
+    }
+}
+
+/// Receives the events of a run, stamped with the tick they happened at.
+pub trait EventSink {
+    fn emit(&mut self, timestamp: usize, event: &SimEvent);
+}
+
+impl EventSink for Vec<(usize, SimEvent)> {
+    fn emit(&mut self, timestamp: usize, event: &SimEvent) {
+        self.push((timestamp, event.clone()));
+    }
+}
+
+/// Renders events as the human readable log printed by the binary.
+#[derive(Default)]
+pub struct TextRenderer {
+    /// Whether the resources of a process leaving are being released; the
+    /// releases are closed by a blank line.
+    releasing: bool,
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl EventSink for TextRenderer {
+    fn emit(&mut self, _timestamp: usize, event: &SimEvent) {
+        let released = matches!(
+            event,
+            SimEvent::ResourceReleased { .. } | SimEvent::Unblocked { .. }
+        );
+        if self.releasing && !released {
+            println!();
+        }
+        self.releasing = matches!(
+            event,
+            SimEvent::Terminated { .. } | SimEvent::Aborted { .. } | SimEvent::RolledBack { .. }
+        ) || (self.releasing && released);
+        match event {
+            SimEvent::FileSystemLoaded { num_blocks, files } => {
+                let alloc_map = files
+                    .iter()
+                    .map(|(file_name, segment)| format!("{:?}: {:?}", file_name, segment))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                println!("FileManager {{ num_blocks: {num_blocks}, alloc_map: {{{alloc_map}}} }}\n")
+            }
+            SimEvent::ProcessCreated {
+                pid,
+                priority,
+                cpu_time,
+                address_space,
+                resources,
+                ..
+            } => {
+                println!("Created process");
+                println!(
+                    "{{ pid: {}, {}, priority: {}, time: {}, instructions: {:?} }}",
+                    pid,
+                    describe(address_space),
+                    priority,
+                    cpu_time,
+                    resources,
+                )
+            }
+            SimEvent::MemoryUnavailable { pid, .. } => {
+                println!(
+                    "Unavailable Memory for process {}. Waiting memory for creation\n",
+                    pid
+                )
+            }
+            SimEvent::MemoryUnsupported { pid, .. } => {
+                println!("Unsupported Memory for process {}. Skipping process\n", pid)
+            }
+            SimEvent::CpuInstruction { pid, .. } => {
+                println!("Process {} CPU instruction\n", pid)
+            }
+            SimEvent::Blocked { pid, resource, .. } => {
+                println!(
+                    "Process {} blocked waiting for resource {:?}\n",
+                    pid, resource
+                )
+            }
+            SimEvent::ResourceGranted { pid, resource, .. } => {
+                println!("Process {} allocated resource {:?}\n", pid, resource)
+            }
+            SimEvent::ResourceReleased { pid, resource } => {
+                println!("Process {} releasing resource {:?}", pid, resource)
+            }
+            SimEvent::Unblocked { pid, .. } => println!("Process {} unblocked", pid),
+            SimEvent::FileCreated {
+                pid,
+                file_name,
+                segment,
+            } => {
+                println!(
+                    "Process {} created file {} with {} blocks\n",
+                    pid, file_name, segment.length
+                )
+            }
+            SimEvent::FileCreateFailed {
+                pid,
+                file_name,
+                blocks,
+            } => {
+                println!(
+                    "Process {} could not create file {} with {} blocks\n",
+                    pid, file_name, blocks
+                )
+            }
+            SimEvent::FileDeleted { pid, file_name, .. } => {
+                println!("Process {} deleted file {}\n", pid, file_name)
+            }
+            SimEvent::FileDeleteDenied { pid, file_name } => {
+                println!("Process {} could not delete file {}\n", pid, file_name)
+            }
+            SimEvent::FileNotFound { pid, file_name } => {
+                println!(
+                    "Process {} could not find file {} to delete\n",
+                    pid, file_name
+                )
+            }
+            SimEvent::Promoted { pid, from, to } => {
+                println!("Process {} promoted from queue {} to {}\n", pid, from, to)
+            }
+            SimEvent::PriorityBoost => println!("Boosting every user process to queue 1\n"),
+            SimEvent::PageHit { pid, page } => {
+                println!("Process {} accessed page {}\n", pid, page)
+            }
+            SimEvent::PageFault {
+                pid,
+                page,
+                frame,
+                evicted,
+            } => match evicted {
+                Some(evicted) => println!(
+                    "Process {} page fault on page {}, replacing page {} in frame {}\n",
+                    pid, page, evicted, frame
+                ),
+                None => println!(
+                    "Process {} page fault on page {}, loaded in frame {}\n",
+                    pid, page, frame
+                ),
+            },
+            SimEvent::SegmentAccessed {
+                pid,
+                segment,
+                offset,
+                address,
+            } => println!(
+                "Process {} accessed {} offset {} at block {}\n",
+                pid,
+                segment.name(),
+                offset,
+                address
+            ),
+            SimEvent::ProtectionFault {
+                pid,
+                segment,
+                offset,
+                limit,
+                ..
+            } => println!(
+                "Process {} protection fault: {} offset {} is out of its {} blocks\n",
+                pid,
+                segment.name(),
+                offset,
+                limit
+            ),
+            SimEvent::MemoryResized { pid, from, to, .. } => {
+                let change = match to.length > from.length {
+                    true => "grew",
+                    false => "shrank",
+                };
+                match from.offset == to.offset {
+                    true => println!(
+                        "Process {} {} its memory to {} blocks in place {{ offset: {} }}\n",
+                        pid, change, to.length, to.offset
+                    ),
+                    false => println!(
+                        "Process {} {} its memory to {} blocks, moved from offset {} to {}\n",
+                        pid, change, to.length, from.offset, to.offset
+                    ),
+                }
+            }
+            SimEvent::BlockedOnMemory { pid, blocks, .. } => println!(
+                "Process {} blocked waiting for its memory to grow to {} blocks\n",
+                pid, blocks
+            ),
+            SimEvent::MemoryResizeDenied { pid, blocks, .. } => println!(
+                "Process {} memory can never be resized to {} blocks, the request is ignored\n",
+                pid, blocks
+            ),
+            SimEvent::Deadlocked { pids, resources } => println!(
+                "Deadlock between processes {:?} over resources {:?}\n",
+                pids, resources
+            ),
+            SimEvent::SafeSequence {
+                pid,
+                resource,
+                sequence,
+            } => println!(
+                "Granting {:?} to process {} leaves the safe sequence {:?}\n",
+                resource, pid, sequence
+            ),
+            SimEvent::ClaimExceeded { pid, resource, .. } => println!(
+                "Process {} requests a {:?} beyond its claim, the request is denied\n",
+                pid, resource
+            ),
+            SimEvent::Stranded { pids } => println!(
+                "No process can run anymore, stopping with processes {:?} left\n",
+                pids
+            ),
+            SimEvent::Aborted { pid, .. } => {
+                println!("Process {} aborted to break the deadlock", pid)
+            }
+            SimEvent::RolledBack { pid, .. } => {
+                println!("Process {} rolled back to request its resources again", pid)
+            }
+            SimEvent::SwappedOut { pid, blocks, .. } => {
+                println!("Process {} swapped out, freeing {} blocks\n", pid, blocks)
+            }
+            SimEvent::SwappedIn {
+                pid, address_space, ..
+            } => println!(
+                "Process {} swapped in {{ {} }}\n",
+                pid,
+                describe(address_space)
+            ),
+            SimEvent::Compacted {
+                area,
+                moved_blocks,
+                relocated,
+                cost,
+            } => println!(
+                "Compacted {}, moving {} blocks of {} processes in {} ticks\n",
+                area.name(),
+                moved_blocks,
+                relocated,
+                cost
+            ),
+            SimEvent::Terminated { pid, .. } => println!("Terminating process {}", pid),
+            SimEvent::Enqueued { .. }
+            | SimEvent::Dispatched { .. }
+            | SimEvent::Preempted { .. }
+            | SimEvent::Executed { .. }
+            | SimEvent::Idle
+            | SimEvent::FreeSpaceSampled { .. } => {}
+        }
+    }
+}
+
+fn describe(address_space: &AddressSpace) -> String {
+    match address_space {
+        AddressSpace::Contiguous(segment) => {
+            format!("offset: {}, blocks: {}", segment.offset, segment.length)
+        }
+        AddressSpace::Paged(page_table) => format!(
+            "pages: {}, frames: {:?}",
+            page_table.pages(),
+            page_table.frames()
+        ),
+        AddressSpace::Segmented(segment_table) => segment_table
+            .entries()
+            .iter()
+            .map(|(kind, segment)| {
+                format!(
+                    "{}: {{ offset: {}, blocks: {} }}",
+                    kind.name(),
+                    segment.offset,
+                    segment.length
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", "),
+        AddressSpace::Swapped(request) => format!("swapped: {}", request.blocks()),
+    }
+}
@@ -0,0 +1,553 @@
+use std::io::Write;
+
+use crate::memory::AddressSpace;
+use crate::resources::Resource;
+use crate::structures::segment_list::Segment;
+
+use super::{EventSink, SimEvent, StorageArea};
+
+/// Writes every event as one JSON object per line.
+pub struct JsonlRenderer<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonlRenderer<W> {
+    pub fn new(writer: W) -> Self {
+        JsonlRenderer { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> EventSink for JsonlRenderer<W> {
+    fn emit(&mut self, timestamp: usize, event: &SimEvent) {
+        let line = render(timestamp, event);
+        writeln!(self.writer, "{}", line).expect("could not write event");
+    }
+}
+
+struct JsonObject {
+    fields: Vec<String>,
+}
+
+impl JsonObject {
+    fn new() -> Self {
+        JsonObject { fields: Vec::new() }
+    }
+
+    fn raw(mut self, key: &str, value: String) -> Self {
+        self.fields.push(format!("{}:{}", quote(key), value));
+        self
+    }
+
+    fn number(self, key: &str, value: usize) -> Self {
+        self.raw(key, value.to_string())
+    }
+
+    fn float(self, key: &str, value: f64) -> Self {
+        self.raw(key, format!("{:.4}", value))
+    }
+
+    fn optional_number(self, key: &str, value: Option<usize>) -> Self {
+        match value {
+            Some(value) => self.number(key, value),
+            None => self.raw(key, String::from("null")),
+        }
+    }
+
+    fn string(self, key: &str, value: &str) -> Self {
+        self.raw(key, quote(value))
+    }
+
+    fn file_name(self, file_name: char) -> Self {
+        self.string("file_name", &file_name.to_string())
+    }
+
+    fn resource(self, resource: Resource) -> Self {
+        self.string("resource", resource.name())
+    }
+
+    fn area(self, area: StorageArea) -> Self {
+        self.string("area", area.name())
+    }
+
+    fn segment(self, segment: &Segment) -> Self {
+        self.number("offset", segment.offset)
+            .number("blocks", segment.length)
+    }
+
+    fn address_space(self, address_space: &AddressSpace) -> Self {
+        match address_space {
+            AddressSpace::Contiguous(segment) => self.segment(segment),
+            AddressSpace::Paged(page_table) => {
+                let frames = page_table
+                    .frames()
+                    .iter()
+                    .map(|frame| frame.to_string())
+                    .collect::<Vec<_>>();
+                self.number("pages", page_table.pages())
+                    .raw("frames", format!("[{}]", frames.join(",")))
+            }
+            AddressSpace::Segmented(segment_table) => {
+                let segments = segment_table
+                    .entries()
+                    .iter()
+                    .map(|(kind, segment)| {
+                        JsonObject::new()
+                            .string("segment", kind.name())
+                            .segment(segment)
+                            .build()
+                    })
+                    .collect::<Vec<_>>();
+                self.raw("segments", format!("[{}]", segments.join(",")))
+            }
+            AddressSpace::Swapped(request) => self.number("swapped_blocks", request.blocks()),
+        }
+    }
+
+    fn build(self) -> String {
+        format!("{{{}}}", self.fields.join(","))
+    }
+}
+
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for character in value.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            character if (character as u32) < 0x20 => {
+                quoted.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn render(timestamp: usize, event: &SimEvent) -> String {
+    let object = JsonObject::new().number("timestamp", timestamp);
+    match event {
+        SimEvent::FileSystemLoaded { num_blocks, files } => {
+            let files = files
+                .iter()
+                .map(|(file_name, segment)| {
+                    JsonObject::new()
+                        .file_name(*file_name)
+                        .segment(segment)
+                        .build()
+                })
+                .collect::<Vec<_>>();
+            object
+                .string("event", "file_system_loaded")
+                .number("num_blocks", *num_blocks)
+                .raw("files", format!("[{}]", files.join(",")))
+        }
+        SimEvent::ProcessCreated {
+            pid,
+            priority,
+            arrival_time,
+            cpu_time,
+            address_space,
+            resources,
+        } => {
+            let resources = resources
+                .iter()
+                .map(|resource| quote(resource.name()))
+                .collect::<Vec<_>>();
+            object
+                .string("event", "process_created")
+                .number("pid", *pid)
+                .number("priority", *priority)
+                .number("arrival_time", *arrival_time)
+                .number("cpu_time", *cpu_time)
+                .address_space(address_space)
+                .raw("resources", format!("[{}]", resources.join(",")))
+        }
+        SimEvent::MemoryUnavailable {
+            pid,
+            priority,
+            blocks,
+        } => object
+            .string("event", "memory_unavailable")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .number("blocks", *blocks),
+        SimEvent::MemoryUnsupported {
+            pid,
+            priority,
+            blocks,
+        } => object
+            .string("event", "memory_unsupported")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .number("blocks", *blocks),
+        SimEvent::Enqueued { pid, priority } => object
+            .string("event", "enqueued")
+            .number("pid", *pid)
+            .number("priority", *priority),
+        SimEvent::Dispatched { pid, priority } => object
+            .string("event", "dispatched")
+            .number("pid", *pid)
+            .number("priority", *priority),
+        SimEvent::Preempted { pid, priority } => object
+            .string("event", "preempted")
+            .number("pid", *pid)
+            .number("priority", *priority),
+        SimEvent::Promoted { pid, from, to } => object
+            .string("event", "promoted")
+            .number("pid", *pid)
+            .number("from", *from)
+            .number("to", *to),
+        SimEvent::PriorityBoost => object.string("event", "priority_boost"),
+        SimEvent::Executed { pid, priority } => object
+            .string("event", "executed")
+            .number("pid", *pid)
+            .number("priority", *priority),
+        SimEvent::Idle => object.string("event", "idle"),
+        SimEvent::CpuInstruction { pid, priority } => object
+            .string("event", "cpu_instruction")
+            .number("pid", *pid)
+            .number("priority", *priority),
+        SimEvent::Blocked {
+            pid,
+            priority,
+            resource,
+        } => object
+            .string("event", "blocked")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .resource(*resource),
+        SimEvent::ResourceGranted {
+            pid,
+            priority,
+            resource,
+        } => object
+            .string("event", "resource_granted")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .resource(*resource),
+        SimEvent::ResourceReleased { pid, resource } => object
+            .string("event", "resource_released")
+            .number("pid", *pid)
+            .resource(*resource),
+        SimEvent::Unblocked {
+            pid,
+            priority,
+            resource,
+        } => object
+            .string("event", "unblocked")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .resource(*resource),
+        SimEvent::FileCreated {
+            pid,
+            file_name,
+            segment,
+        } => object
+            .string("event", "file_created")
+            .number("pid", *pid)
+            .file_name(*file_name)
+            .segment(segment),
+        SimEvent::FileCreateFailed {
+            pid,
+            file_name,
+            blocks,
+        } => object
+            .string("event", "file_create_failed")
+            .number("pid", *pid)
+            .file_name(*file_name)
+            .number("blocks", *blocks),
+        SimEvent::FileDeleted {
+            pid,
+            file_name,
+            segment,
+        } => object
+            .string("event", "file_deleted")
+            .number("pid", *pid)
+            .file_name(*file_name)
+            .segment(segment),
+        SimEvent::FileDeleteDenied { pid, file_name } => object
+            .string("event", "file_delete_denied")
+            .number("pid", *pid)
+            .file_name(*file_name),
+        SimEvent::FileNotFound { pid, file_name } => object
+            .string("event", "file_not_found")
+            .number("pid", *pid)
+            .file_name(*file_name),
+        SimEvent::SwappedOut {
+            pid,
+            priority,
+            blocks,
+        } => object
+            .string("event", "swapped_out")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .number("blocks", *blocks),
+        SimEvent::SwappedIn {
+            pid,
+            priority,
+            address_space,
+        } => object
+            .string("event", "swapped_in")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .address_space(address_space),
+        SimEvent::Terminated {
+            pid,
+            priority,
+            address_space,
+        } => object
+            .string("event", "terminated")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .address_space(address_space),
+        SimEvent::PageHit { pid, page } => object
+            .string("event", "page_hit")
+            .number("pid", *pid)
+            .number("page", *page),
+        SimEvent::PageFault {
+            pid,
+            page,
+            frame,
+            evicted,
+        } => object
+            .string("event", "page_fault")
+            .number("pid", *pid)
+            .number("page", *page)
+            .number("frame", *frame)
+            .optional_number("evicted", *evicted),
+        SimEvent::SegmentAccessed {
+            pid,
+            segment,
+            offset,
+            address,
+        } => object
+            .string("event", "segment_accessed")
+            .number("pid", *pid)
+            .string("segment", segment.name())
+            .number("offset", *offset)
+            .number("address", *address),
+        SimEvent::ProtectionFault {
+            pid,
+            priority,
+            segment,
+            offset,
+            limit,
+        } => object
+            .string("event", "protection_fault")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .string("segment", segment.name())
+            .number("offset", *offset)
+            .number("limit", *limit),
+        SimEvent::MemoryResized {
+            pid,
+            priority,
+            from,
+            to,
+        } => object
+            .string("event", "memory_resized")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .number("from_offset", from.offset)
+            .number("from_blocks", from.length)
+            .segment(to),
+        SimEvent::BlockedOnMemory {
+            pid,
+            priority,
+            blocks,
+        } => object
+            .string("event", "blocked_on_memory")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .number("blocks", *blocks),
+        SimEvent::MemoryResizeDenied {
+            pid,
+            priority,
+            blocks,
+        } => object
+            .string("event", "memory_resize_denied")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .number("blocks", *blocks),
+        SimEvent::Deadlocked { pids, resources } => {
+            let pids = pids.iter().map(usize::to_string).collect::<Vec<_>>();
+            let resources = resources
+                .iter()
+                .map(|resource| quote(resource.name()))
+                .collect::<Vec<_>>();
+            object
+                .string("event", "deadlocked")
+                .raw("pids", format!("[{}]", pids.join(",")))
+                .raw("resources", format!("[{}]", resources.join(",")))
+        }
+        SimEvent::SafeSequence {
+            pid,
+            resource,
+            sequence,
+        } => {
+            let sequence = sequence.iter().map(usize::to_string).collect::<Vec<_>>();
+            object
+                .string("event", "safe_sequence")
+                .number("pid", *pid)
+                .resource(*resource)
+                .raw("sequence", format!("[{}]", sequence.join(",")))
+        }
+        SimEvent::ClaimExceeded {
+            pid,
+            priority,
+            resource,
+        } => object
+            .string("event", "claim_exceeded")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .resource(*resource),
+        SimEvent::Stranded { pids } => {
+            let pids = pids.iter().map(usize::to_string).collect::<Vec<_>>();
+            object
+                .string("event", "stranded")
+                .raw("pids", format!("[{}]", pids.join(",")))
+        }
+        SimEvent::Aborted {
+            pid,
+            priority,
+            address_space,
+        } => object
+            .string("event", "aborted")
+            .number("pid", *pid)
+            .number("priority", *priority)
+            .address_space(address_space),
+        SimEvent::RolledBack { pid, priority } => object
+            .string("event", "rolled_back")
+            .number("pid", *pid)
+            .number("priority", *priority),
+        SimEvent::Compacted {
+            area,
+            moved_blocks,
+            relocated,
+            cost,
+        } => object
+            .string("event", "compacted")
+            .area(*area)
+            .number("moved_blocks", *moved_blocks)
+            .number("relocated", *relocated)
+            .number("cost", *cost),
+        SimEvent::FreeSpaceSampled { area, space } => object
+            .string("event", "free_space")
+            .area(*area)
+            .number("capacity", space.capacity)
+            .number("free_blocks", space.free_blocks)
+            .number("holes", space.holes)
+            .number("largest_hole", space.largest_hole)
+            .float("fragmentation", space.fragmentation())
+            .number("internal_fragmentation", space.internal_fragmentation),
+    }
+    .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::structures::segment_list::FreeSpace;
+
+    use super::*;
+
+    fn render_to_string(events: &[(usize, SimEvent)]) -> String {
+        let mut renderer = JsonlRenderer::new(Vec::new());
+        for (timestamp, event) in events {
+            renderer.emit(*timestamp, event);
+        }
+        String::from_utf8(renderer.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn one_object_per_line() {
+        let output = render_to_string(&[
+            (
+                0,
+                SimEvent::Dispatched {
+                    pid: 1,
+                    priority: 2,
+                },
+            ),
+            (
+                1,
+                SimEvent::Blocked {
+                    pid: 1,
+                    priority: 2,
+                    resource: Resource::SataDevice,
+                },
+            ),
+        ]);
+        assert_eq!(
+            output,
+            "{\"timestamp\":0,\"event\":\"dispatched\",\"pid\":1,\"priority\":2}\n\
+             {\"timestamp\":1,\"event\":\"blocked\",\"pid\":1,\"priority\":2,\"resource\":\"sata\"}\n"
+        );
+    }
+
+    #[test]
+    fn segments_and_lists_are_rendered() {
+        let output = render_to_string(&[(
+            3,
+            SimEvent::ProcessCreated {
+                pid: 0,
+                priority: 1,
+                arrival_time: 2,
+                cpu_time: 5,
+                address_space: AddressSpace::Contiguous(Segment {
+                    offset: 64,
+                    length: 8,
+                }),
+                resources: vec![Resource::Scanner, Resource::Modem],
+            },
+        )]);
+        assert_eq!(
+            output,
+            "{\"timestamp\":3,\"event\":\"process_created\",\"pid\":0,\"priority\":1,\
+             \"arrival_time\":2,\"cpu_time\":5,\"offset\":64,\"blocks\":8,\
+             \"resources\":[\"scanner\",\"modem\"]}\n"
+        );
+    }
+
+    #[test]
+    fn free_space_has_its_fragmentation() {
+        let output = render_to_string(&[(
+            4,
+            SimEvent::FreeSpaceSampled {
+                area: StorageArea::Disk,
+                space: FreeSpace {
+                    capacity: 10,
+                    free_blocks: 4,
+                    holes: 2,
+                    largest_hole: 3,
+                    internal_fragmentation: 1,
+                },
+            },
+        )]);
+        assert_eq!(
+            output,
+            "{\"timestamp\":4,\"event\":\"free_space\",\"area\":\"disk\",\"capacity\":10,\
+             \"free_blocks\":4,\"holes\":2,\"largest_hole\":3,\"fragmentation\":0.2500,\"internal_fragmentation\":1}\n"
+        );
+    }
+
+    #[test]
+    fn file_names_are_escaped() {
+        let output = render_to_string(&[(
+            0,
+            SimEvent::FileNotFound {
+                pid: 0,
+                file_name: '"',
+            },
+        )]);
+        assert_eq!(
+            output,
+            "{\"timestamp\":0,\"event\":\"file_not_found\",\"pid\":0,\"file_name\":\"\\\"\"}\n"
+        );
+    }
+}
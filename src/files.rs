@@ -1,12 +1,16 @@
 use std::collections::HashMap;
 
+use crate::config::DiskConfig;
+use crate::events::SimEvent;
 use crate::process::Process;
-use crate::structures::segment_list::{Segment, SegmentList};
+use crate::structures::segment_list::{FreeSpace, Segment, SegmentList};
 
 #[derive(Debug)]
 pub struct FileManager {
     free_segments: SegmentList,
     alloc_map: HashMap<char, Segment>,
+    /// Check the free list after every operation.
+    paranoid: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -16,25 +20,56 @@ pub enum DeleteFileError {
 }
 
 impl FileManager {
-    pub fn new(num_blocks: usize, alloc_disk_blocks: Vec<(char, Segment)>) -> FileManager {
+    pub fn new(
+        num_blocks: usize,
+        alloc_disk_blocks: Vec<(char, Segment)>,
+        config: &DiskConfig,
+    ) -> FileManager {
         let initial_segment = Segment {
             offset: 0,
             length: num_blocks,
         };
-        let mut free_segments = SegmentList::new(vec![initial_segment]);
+        let mut free_segments = SegmentList::with_policy(vec![initial_segment], config.allocation);
         for (_, alloc_segment) in alloc_disk_blocks.iter() {
             free_segments.alloc_segment(alloc_segment);
         }
         let alloc_map = alloc_disk_blocks.into_iter().collect();
-        let file_manager = FileManager {
+        FileManager {
             free_segments,
             alloc_map,
-        };
-        println!(
-            "FileManager {{ num_blocks: {num_blocks}, alloc_map: {:?} }}\n",
-            file_manager.alloc_map
-        );
-        file_manager
+            paranoid: false,
+        }
+    }
+
+    /// Makes every operation check the free list afterwards, panicking on the
+    /// first broken invariant. The pre-allocated files are checked at once.
+    pub fn set_paranoid(&mut self, paranoid: bool) {
+        self.paranoid = paranoid;
+        self.check_invariants();
+    }
+
+    fn check_invariants(&self) {
+        if !self.paranoid {
+            return;
+        }
+        if let Err(error) = self.free_segments.check_invariants() {
+            panic!("disk: {}", error);
+        }
+    }
+
+    /// Files currently on disk, sorted by their offset.
+    pub fn files(&self) -> Vec<(char, Segment)> {
+        let mut files: Vec<(char, Segment)> = self
+            .alloc_map
+            .iter()
+            .map(|(file_name, segment)| (*file_name, segment.clone()))
+            .collect();
+        files.sort_by_key(|(_, segment)| segment.offset);
+        files
+    }
+
+    pub fn free_space(&self) -> FreeSpace {
+        self.free_segments.free_space()
     }
 
     pub fn create_file(
@@ -42,20 +77,25 @@ impl FileManager {
         process: &mut Process,
         file_name: char,
         num_blocks: usize,
+        events: &mut Vec<SimEvent>,
     ) -> Option<Segment> {
-        if let Some(alloc_segment) = self.free_segments.alloc(num_blocks) {
-            println!(
-                "Process {} created file {} with {} blocks\n",
-                process.software_context.id, file_name, num_blocks
-            );
+        let alloc_segment = self.free_segments.alloc(num_blocks);
+        self.check_invariants();
+        if let Some(alloc_segment) = alloc_segment {
+            events.push(SimEvent::FileCreated {
+                pid: process.software_context.id,
+                file_name,
+                segment: alloc_segment.clone(),
+            });
             self.alloc_map.insert(file_name, alloc_segment.clone());
             process.software_context.files_created.push(file_name);
             Some(alloc_segment)
         } else {
-            println!(
-                "Process {} could not create file {} with {} blocks\n",
-                process.software_context.id, file_name, num_blocks
-            );
+            events.push(SimEvent::FileCreateFailed {
+                pid: process.software_context.id,
+                file_name,
+                blocks: num_blocks,
+            });
             None
         }
     }
@@ -64,15 +104,16 @@ impl FileManager {
         &mut self,
         process: &Process,
         file_name: char,
+        events: &mut Vec<SimEvent>,
     ) -> Result<(), DeleteFileError> {
         let is_real_time_process = process.software_context.priority == 0;
         let process_created_file = process.software_context.files_created.contains(&file_name);
         let is_authorized = is_real_time_process || process_created_file;
         if !is_authorized {
-            println!(
-                "Process {} could not delete file {}\n",
-                process.software_context.id, file_name
-            );
+            events.push(SimEvent::FileDeleteDenied {
+                pid: process.software_context.id,
+                file_name,
+            });
             return Err(DeleteFileError::Unauthorized);
         }
         let disk_segment = self
@@ -81,19 +122,21 @@ impl FileManager {
             .ok_or(DeleteFileError::NotFound);
         match disk_segment {
             Ok(disk_segment) => {
-                println!(
-                    "Process {} deleted file {}\n",
-                    process.software_context.id, file_name
-                );
+                events.push(SimEvent::FileDeleted {
+                    pid: process.software_context.id,
+                    file_name,
+                    segment: disk_segment.clone(),
+                });
                 self.free_segments.free(disk_segment);
-                return Ok(());
+                self.check_invariants();
+                Ok(())
             }
             Err(_) => {
-                println!(
-                    "Process {} could not find file {} to delete\n",
-                    process.software_context.id, file_name
-                );
-                return Err(DeleteFileError::NotFound);
+                events.push(SimEvent::FileNotFound {
+                    pid: process.software_context.id,
+                    file_name,
+                });
+                Err(DeleteFileError::NotFound)
             }
         }
     }
@@ -101,6 +144,8 @@ impl FileManager {
 
 #[cfg(test)]
 mod tests {
+    use crate::memory::AddressSpace;
+
     use super::*;
 
     fn create_process_mock(priority: usize) -> Process {
@@ -113,10 +158,12 @@ mod tests {
             false,
             false,
             vec![],
-            Segment {
+            vec![],
+            vec![],
+            AddressSpace::Contiguous(Segment {
                 offset: 0,
                 length: 0,
-            },
+            }),
         )
     }
 
@@ -125,9 +172,9 @@ mod tests {
 
         #[test]
         fn create_file_success() {
-            let mut file_manager = FileManager::new(6, vec![]);
+            let mut file_manager = FileManager::new(6, vec![], &DiskConfig::default());
             let mut process: Process = create_process_mock(0);
-            let result = file_manager.create_file(&mut process, 'A', 3);
+            let result = file_manager.create_file(&mut process, 'A', 3, &mut vec![]);
             assert_eq!(
                 result,
                 Some(Segment {
@@ -140,9 +187,12 @@ mod tests {
 
         #[test]
         fn test_create_file_no_space() {
-            let mut file_manager = FileManager::new(6, vec![]);
+            let mut file_manager = FileManager::new(6, vec![], &DiskConfig::default());
             let mut process = create_process_mock(0);
-            assert_eq!(file_manager.create_file(&mut process, 'A', 7), None);
+            assert_eq!(
+                file_manager.create_file(&mut process, 'A', 7, &mut vec![]),
+                None
+            );
         }
     }
 
@@ -151,9 +201,9 @@ mod tests {
 
         #[test]
         fn file_not_found() {
-            let mut file_manager = FileManager::new(6, vec![]);
+            let mut file_manager = FileManager::new(6, vec![], &DiskConfig::default());
             let real_time_process = create_process_mock(0);
-            let result = file_manager.delete_file(&real_time_process, 'A');
+            let result = file_manager.delete_file(&real_time_process, 'A', &mut vec![]);
             assert_eq!(result, Err(DeleteFileError::NotFound));
         }
 
@@ -168,20 +218,23 @@ mod tests {
                         length: 3,
                     },
                 )],
+                &DiskConfig::default(),
             );
             let user_process = create_process_mock(1);
-            let result = file_manager.delete_file(&user_process, 'A');
+            let result = file_manager.delete_file(&user_process, 'A', &mut vec![]);
             assert_eq!(result, Err(DeleteFileError::Unauthorized));
         }
 
         #[test]
         fn user_process_authorized() {
-            let mut file_manager = FileManager::new(6, vec![]);
+            let mut file_manager = FileManager::new(6, vec![], &DiskConfig::default());
             let mut user_process = create_process_mock(1);
             assert!(file_manager
-                .create_file(&mut user_process, 'A', 3)
+                .create_file(&mut user_process, 'A', 3, &mut vec![])
                 .is_some());
-            assert!(file_manager.delete_file(&user_process, 'A').is_ok());
+            assert!(file_manager
+                .delete_file(&user_process, 'A', &mut vec![])
+                .is_ok());
         }
 
         #[test]
@@ -195,9 +248,12 @@ mod tests {
                         length: 3,
                     },
                 )],
+                &DiskConfig::default(),
             );
             let real_time_process = create_process_mock(0);
-            assert!(file_manager.delete_file(&real_time_process, 'A').is_ok());
+            assert!(file_manager
+                .delete_file(&real_time_process, 'A', &mut vec![])
+                .is_ok());
         }
     }
 
@@ -206,34 +262,40 @@ mod tests {
 
         #[test]
         fn adds_and_deletes() {
-            let mut file_manager = FileManager::new(10, {
-                vec![
-                    (
-                        'X',
-                        Segment {
-                            offset: 0,
-                            length: 2,
-                        },
-                    ),
-                    (
-                        'Y',
-                        Segment {
-                            offset: 3,
-                            length: 1,
-                        },
-                    ),
-                    (
-                        'Z',
-                        Segment {
-                            offset: 5,
-                            length: 3,
-                        },
-                    ),
-                ]
-            });
-            let mut process_vec = vec![create_process_mock(0)];
-            assert!(file_manager.delete_file(&process_vec[0], 'X').is_ok());
-            let result = file_manager.create_file(&mut process_vec[0], 'D', 3);
+            let mut file_manager = FileManager::new(
+                10,
+                {
+                    vec![
+                        (
+                            'X',
+                            Segment {
+                                offset: 0,
+                                length: 2,
+                            },
+                        ),
+                        (
+                            'Y',
+                            Segment {
+                                offset: 3,
+                                length: 1,
+                            },
+                        ),
+                        (
+                            'Z',
+                            Segment {
+                                offset: 5,
+                                length: 3,
+                            },
+                        ),
+                    ]
+                },
+                &DiskConfig::default(),
+            );
+            let mut process_vec = [create_process_mock(0)];
+            assert!(file_manager
+                .delete_file(&process_vec[0], 'X', &mut vec![])
+                .is_ok());
+            let result = file_manager.create_file(&mut process_vec[0], 'D', 3, &mut vec![]);
             assert_eq!(
                 result,
                 Some(Segment {
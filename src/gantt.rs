@@ -0,0 +1,389 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::events::SimEvent;
+use crate::resources::Resource;
+
+const CELL_WIDTH: usize = 16;
+const ROW_HEIGHT: usize = 20;
+const LABEL_WIDTH: usize = 56;
+const PRIORITY_COLORS: [&str; 4] = ["#d62728", "#ff7f0e", "#2ca02c", "#1f77b4"];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Slot {
+    pub pid: usize,
+    pub priority: usize,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MarkerKind {
+    Block,
+    Unblock,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Marker {
+    pub timestamp: usize,
+    pub pid: usize,
+    pub kind: MarkerKind,
+    pub resource: Resource,
+}
+
+/// Which process held the CPU on every tick of a run, plus block/unblock markers.
+#[derive(Debug, Default)]
+pub struct Timeline {
+    slots: Vec<Option<Slot>>,
+    markers: Vec<Marker>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn slots(&self) -> &[Option<Slot>] {
+        &self.slots
+    }
+
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    pub fn record(&mut self, timestamp: usize, event: &SimEvent) {
+        match event {
+            SimEvent::Executed { pid, priority } => self.set_slot(
+                timestamp,
+                Some(Slot {
+                    pid: *pid,
+                    priority: *priority,
+                }),
+            ),
+            SimEvent::Idle => self.set_slot(timestamp, None),
+            SimEvent::Blocked { pid, resource, .. } => self.markers.push(Marker {
+                timestamp,
+                pid: *pid,
+                kind: MarkerKind::Block,
+                resource: *resource,
+            }),
+            SimEvent::Unblocked { pid, resource, .. } => self.markers.push(Marker {
+                timestamp,
+                pid: *pid,
+                kind: MarkerKind::Unblock,
+                resource: *resource,
+            }),
+            _ => {}
+        }
+    }
+
+    fn set_slot(&mut self, timestamp: usize, slot: Option<Slot>) {
+        if self.slots.len() <= timestamp {
+            self.slots.resize(timestamp + 1, None);
+        }
+        self.slots[timestamp] = slot;
+    }
+
+    fn pids(&self) -> Vec<usize> {
+        let mut pids: Vec<usize> = self
+            .slots
+            .iter()
+            .flatten()
+            .map(|slot| slot.pid)
+            .chain(self.markers.iter().map(|marker| marker.pid))
+            .collect();
+        pids.sort();
+        pids.dedup();
+        pids
+    }
+
+    /// Ticks each process spent blocked, as `[start, end)` intervals.
+    fn blocked_intervals(&self) -> BTreeMap<usize, Vec<(usize, usize)>> {
+        let mut intervals: BTreeMap<usize, Vec<(usize, usize)>> = BTreeMap::new();
+        let mut blocked_since = BTreeMap::new();
+        for marker in self.markers.iter() {
+            match marker.kind {
+                MarkerKind::Block => {
+                    blocked_since.insert(marker.pid, marker.timestamp + 1);
+                }
+                MarkerKind::Unblock => {
+                    if let Some(start) = blocked_since.remove(&marker.pid) {
+                        intervals
+                            .entry(marker.pid)
+                            .or_default()
+                            .push((start, marker.timestamp));
+                    }
+                }
+            }
+        }
+        for (pid, start) in blocked_since {
+            intervals
+                .entry(pid)
+                .or_default()
+                .push((start, self.slots.len().max(start)));
+        }
+        intervals
+    }
+
+    /// Renders a strip of the CPU owner per tick, its queue, and one row per process
+    /// where `#` marks running ticks, `~` blocked ticks, `B` a block and `U` an unblock.
+    pub fn render_ascii(&self) -> String {
+        let ticks = self.slots.len();
+        let mut chart = String::new();
+        let mut axis = String::new();
+        for tick in 0..ticks {
+            axis.push(if tick % 5 == 0 { '|' } else { ' ' });
+        }
+        writeln!(chart, "{:<8}{}", "time", axis).unwrap();
+        let cpu: String = self
+            .slots
+            .iter()
+            .map(|slot| slot.map_or('.', |slot| symbol(slot.pid)))
+            .collect();
+        writeln!(chart, "{:<8}{}", "cpu", cpu).unwrap();
+        let queue: String = self
+            .slots
+            .iter()
+            .map(|slot| slot.map_or('.', |slot| symbol(slot.priority)))
+            .collect();
+        writeln!(chart, "{:<8}{}", "queue", queue).unwrap();
+        let blocked_intervals = self.blocked_intervals();
+        for pid in self.pids() {
+            let mut row = vec![' '; ticks];
+            for (start, end) in blocked_intervals.get(&pid).into_iter().flatten() {
+                for cell in row.iter_mut().take(*end).skip(*start) {
+                    *cell = '~';
+                }
+            }
+            for (tick, slot) in self.slots.iter().enumerate() {
+                if matches!(slot, Some(slot) if slot.pid == pid) {
+                    row[tick] = '#';
+                }
+            }
+            for marker in self.markers.iter().filter(|marker| marker.pid == pid) {
+                if marker.timestamp < ticks {
+                    row[marker.timestamp] = match marker.kind {
+                        MarkerKind::Block => 'B',
+                        MarkerKind::Unblock => 'U',
+                    };
+                }
+            }
+            let row: String = row.into_iter().collect();
+            writeln!(chart, "{:<8}{}", format!("p{}", pid), row.trim_end()).unwrap();
+        }
+        chart
+    }
+
+    /// Renders a standalone SVG with the CPU strip and one row per process, coloured
+    /// by the queue each tick ran from.
+    pub fn render_svg(&self) -> String {
+        let ticks = self.slots.len();
+        let pids = self.pids();
+        let width = LABEL_WIDTH + ticks * CELL_WIDTH + CELL_WIDTH;
+        let height = ROW_HEIGHT * (pids.len() + 3);
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             font-family=\"monospace\" font-size=\"11\">"
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<rect width=\"{width}\" height=\"{height}\" fill=\"white\"/>"
+        )
+        .unwrap();
+        for tick in (0..=ticks).step_by(5) {
+            let x = LABEL_WIDTH + tick * CELL_WIDTH;
+            writeln!(
+                svg,
+                "<line x1=\"{x}\" y1=\"{}\" x2=\"{x}\" y2=\"{height}\" stroke=\"#ddd\"/>\
+                 <text x=\"{x}\" y=\"{}\">{tick}</text>",
+                ROW_HEIGHT,
+                ROW_HEIGHT - 6
+            )
+            .unwrap();
+        }
+        let cpu_y = ROW_HEIGHT;
+        writeln!(svg, "{}", label(cpu_y, "cpu")).unwrap();
+        for (tick, slot) in self.slots.iter().enumerate() {
+            if let Some(slot) = slot {
+                writeln!(
+                    svg,
+                    "{}<text x=\"{}\" y=\"{}\" fill=\"white\">{}</text>",
+                    cell(tick, cpu_y, slot),
+                    LABEL_WIDTH + tick * CELL_WIDTH + 3,
+                    cpu_y + ROW_HEIGHT - 6,
+                    slot.pid
+                )
+                .unwrap();
+            }
+        }
+        let blocked_intervals = self.blocked_intervals();
+        for (row, pid) in pids.iter().enumerate() {
+            let y = ROW_HEIGHT * (row + 2);
+            writeln!(svg, "{}", label(y, &format!("p{}", pid))).unwrap();
+            for (start, end) in blocked_intervals.get(pid).into_iter().flatten() {
+                writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#bbb\"><title>blocked</title></rect>",
+                    LABEL_WIDTH + start * CELL_WIDTH,
+                    y + ROW_HEIGHT / 3,
+                    (end - start) * CELL_WIDTH,
+                    ROW_HEIGHT / 3
+                )
+                .unwrap();
+            }
+            for (tick, slot) in self.slots.iter().enumerate() {
+                if let Some(slot) = slot.filter(|slot| slot.pid == *pid) {
+                    writeln!(svg, "{}", cell(tick, y, &slot)).unwrap();
+                }
+            }
+            for marker in self.markers.iter().filter(|marker| marker.pid == *pid) {
+                let (color, name) = match marker.kind {
+                    MarkerKind::Block => ("black", "block"),
+                    MarkerKind::Unblock => ("#2ca02c", "unblock"),
+                };
+                let x = LABEL_WIDTH + marker.timestamp * CELL_WIDTH + CELL_WIDTH / 2;
+                writeln!(
+                    svg,
+                    "<path d=\"M{} {} l5 -6 h-10 z\" fill=\"{color}\"><title>{name} {} at {}</title></path>",
+                    x,
+                    y + 6,
+                    marker.resource.name(),
+                    marker.timestamp
+                )
+                .unwrap();
+            }
+        }
+        let legend_y = ROW_HEIGHT * (pids.len() + 2) + ROW_HEIGHT - 6;
+        for (priority, color) in PRIORITY_COLORS.iter().enumerate() {
+            let x = LABEL_WIDTH + priority * 5 * CELL_WIDTH;
+            writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{}\" width=\"10\" height=\"10\" fill=\"{color}\"/>\
+                 <text x=\"{}\" y=\"{legend_y}\">queue {priority}</text>",
+                legend_y - 9,
+                x + 14
+            )
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn symbol(value: usize) -> char {
+    std::char::from_digit(value as u32 % 36, 36).unwrap()
+}
+
+fn label(y: usize, text: &str) -> String {
+    format!("<text x=\"4\" y=\"{}\">{}</text>", y + ROW_HEIGHT - 6, text)
+}
+
+fn cell(tick: usize, y: usize, slot: &Slot) -> String {
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"white\">\
+         <title>t={} pid={} queue={}</title></rect>",
+        LABEL_WIDTH + tick * CELL_WIDTH,
+        y + 2,
+        CELL_WIDTH,
+        ROW_HEIGHT - 4,
+        PRIORITY_COLORS[slot.priority % PRIORITY_COLORS.len()],
+        tick,
+        slot.pid,
+        slot.priority
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_timeline() -> Timeline {
+        let mut timeline = Timeline::new();
+        let events = [
+            (0, SimEvent::Idle),
+            (
+                1,
+                SimEvent::Executed {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (
+                1,
+                SimEvent::Blocked {
+                    pid: 0,
+                    priority: 1,
+                    resource: Resource::Modem,
+                },
+            ),
+            (
+                2,
+                SimEvent::Executed {
+                    pid: 1,
+                    priority: 0,
+                },
+            ),
+            (
+                3,
+                SimEvent::Executed {
+                    pid: 1,
+                    priority: 0,
+                },
+            ),
+            (
+                3,
+                SimEvent::Unblocked {
+                    pid: 0,
+                    priority: 1,
+                    resource: Resource::Modem,
+                },
+            ),
+            (
+                4,
+                SimEvent::Executed {
+                    pid: 0,
+                    priority: 2,
+                },
+            ),
+        ];
+        for (timestamp, event) in events.iter() {
+            timeline.record(*timestamp, event);
+        }
+        timeline
+    }
+
+    #[test]
+    fn records_idle_and_busy_ticks() {
+        let timeline = create_timeline();
+        assert_eq!(timeline.slots().len(), 5);
+        assert_eq!(timeline.slots()[0], None);
+        assert_eq!(
+            timeline.slots()[4],
+            Some(Slot {
+                pid: 0,
+                priority: 2
+            })
+        );
+        assert_eq!(timeline.markers().len(), 2);
+    }
+
+    #[test]
+    fn renders_ascii_strip() {
+        let chart = create_timeline().render_ascii();
+        let lines: Vec<&str> = chart.lines().collect();
+        assert_eq!(lines[1], "cpu     .0110");
+        assert_eq!(lines[2], "queue   .1002");
+        assert_eq!(lines[3], "p0       B~U#");
+        assert_eq!(lines[4], "p1        ##");
+    }
+
+    #[test]
+    fn renders_svg_document() {
+        let svg = create_timeline().render_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains(PRIORITY_COLORS[2]));
+        assert!(svg.contains("block modem at 1"));
+    }
+}
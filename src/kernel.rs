@@ -0,0 +1,1197 @@
+use std::collections::VecDeque;
+
+use crate::config::Config;
+use crate::dispatcher::Dispatcher;
+use crate::events::{EventSink, SimEvent, StorageArea};
+use crate::files::FileManager;
+use crate::gantt::Timeline;
+use crate::memory::{AllocMemoryError, MemoryManager};
+use crate::paging::PageAccess;
+use crate::parsers::{files_parser::DiskOperationDefinition, processes_parser::ProcessDefinition};
+use crate::process::{DiskOperation, Interruption, MemoryOperation, Process};
+use crate::queues::ProcessManager;
+use crate::resources::{DeadlockRecovery, ResourceManager};
+use crate::stats::Statistics;
+use crate::structures::segment_list::Segment;
+use crate::swapper::Swapper;
+
+/// Owns every manager of the simulated operating system and advances them
+/// together, one tick at a time.
+pub struct Kernel {
+    dispatcher: Dispatcher,
+    process_manager: ProcessManager,
+    memory_manager: MemoryManager,
+    resource_manager: ResourceManager,
+    file_manager: FileManager,
+    swapper: Swapper,
+    /// Processes blocked until their memory can grow to the given length.
+    waiting_to_grow: VecDeque<(Process, usize)>,
+    /// Whether the processes left can never run again.
+    stalled: bool,
+    timestamp: usize,
+    events: Vec<SimEvent>,
+    sink: Box<dyn EventSink>,
+    statistics: Statistics,
+    timeline: Timeline,
+}
+
+impl Kernel {
+    pub fn new(
+        processes_definitions: Vec<ProcessDefinition>,
+        num_blocks: usize,
+        alloc_disk_blocks: Vec<(char, Segment)>,
+        disk_operation_definitions: Vec<DiskOperationDefinition>,
+        config: &Config,
+        sink: Box<dyn EventSink>,
+    ) -> Self {
+        let mut file_manager = FileManager::new(num_blocks, alloc_disk_blocks, &config.disk);
+        file_manager.set_paranoid(config.paranoid);
+        let mut memory_manager = MemoryManager::new(&config.memory);
+        memory_manager.set_paranoid(config.paranoid);
+        let mut resource_manager = ResourceManager::new(&config.resources);
+        resource_manager.set_verbose(config.verbose);
+        let mut kernel = Kernel {
+            dispatcher: Dispatcher::new(processes_definitions, disk_operation_definitions),
+            process_manager: ProcessManager::with_scheduler(config.scheduler.build()),
+            memory_manager,
+            resource_manager,
+            events: vec![SimEvent::FileSystemLoaded {
+                num_blocks,
+                files: file_manager.files(),
+            }],
+            file_manager,
+            swapper: Swapper::new(&config.swap),
+            waiting_to_grow: VecDeque::new(),
+            stalled: false,
+            timestamp: 0,
+            sink,
+            statistics: Statistics::new(),
+            timeline: Timeline::new(),
+        };
+        kernel.flush_events();
+        kernel
+    }
+
+    pub fn timestamp(&self) -> usize {
+        self.timestamp
+    }
+
+    pub fn statistics(&self) -> &Statistics {
+        &self.statistics
+    }
+
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.stalled
+            || (!self.dispatcher.has_more_processes()
+                && !self.process_manager.has_more_processes()
+                && !self.swapper.has_processes()
+                && self.waiting_to_grow.is_empty())
+    }
+
+    /// Advances the simulation by a single tick.
+    pub fn step(&mut self) {
+        let swapped_in_processes =
+            self.swapper
+                .on_tick(self.timestamp, &mut self.memory_manager, &mut self.events);
+        for process in swapped_in_processes {
+            self.process_manager
+                .unblock_process(process, self.timestamp, &mut self.events);
+        }
+        self.retry_growths();
+        if let Some((priority, blocks)) = self.dispatcher.waiting_for_memory(self.timestamp) {
+            if self.memory_manager.needs_compaction(priority, blocks) {
+                self.compact_partition(priority);
+            }
+        }
+        let new_processes = self.dispatcher.generate_new_processes(
+            &mut self.memory_manager,
+            self.timestamp,
+            &mut self.events,
+        );
+        for process in new_processes {
+            self.resource_manager.register_claim(&process);
+            self.process_manager
+                .add_process(process, self.timestamp, &mut self.events);
+        }
+        self.swapper.swap_out(
+            self.dispatcher.waiting_for_memory(self.timestamp),
+            self.timestamp,
+            &self.memory_manager,
+            &mut self.resource_manager,
+        );
+
+        let interruption = self
+            .process_manager
+            .get_current_process()
+            .map(|current_process| {
+                (
+                    current_process.software_context.id,
+                    current_process.software_context.priority,
+                    current_process.on_tick(),
+                )
+            });
+        let idle = interruption.is_none();
+        match interruption {
+            Some((pid, priority, interruption)) => {
+                self.events.push(SimEvent::Executed { pid, priority });
+                self.handle_interruption(pid, priority, interruption);
+            }
+            None => self.events.push(SimEvent::Idle),
+        }
+        self.sample_free_space();
+        self.flush_events();
+
+        self.timestamp += 1;
+        self.process_manager
+            .on_tick(self.timestamp, &mut self.events);
+        if idle {
+            self.check_stalled();
+        }
+        self.flush_events();
+    }
+
+    /// Stops the run when no process is left to run, swap or arrive: the
+    /// processes left are blocked on resources or wait for memory, and
+    /// nothing will ever free what they need. An idle tick frees nothing,
+    /// so the next one would go the same way.
+    fn check_stalled(&mut self) {
+        if self.process_manager.has_more_processes()
+            || self.swapper.has_processes()
+            || self.swapper.is_swapping_out()
+            || self.dispatcher.has_arrivals_from(self.timestamp)
+        {
+            return;
+        }
+        let mut pids = self
+            .resource_manager
+            .blocked_processes()
+            .chain(self.waiting_to_grow.iter().map(|(process, _)| process))
+            .map(|process| process.software_context.id)
+            .chain(self.dispatcher.pending_pids())
+            .collect::<Vec<_>>();
+        if pids.is_empty() {
+            return;
+        }
+        pids.sort();
+        self.stalled = true;
+        self.events.push(SimEvent::Stranded { pids });
+    }
+
+    /// Steps until `timestamp` is reached or there is nothing left to run.
+    pub fn run_until(&mut self, timestamp: usize) {
+        while self.timestamp < timestamp && !self.is_finished() {
+            self.step();
+        }
+    }
+
+    /// Steps until every process has been created and terminated.
+    pub fn run(&mut self) {
+        while !self.is_finished() {
+            self.step();
+        }
+    }
+
+    /// Slides the processes of both memory partitions together, merging
+    /// their holes.
+    pub fn compact_memory(&mut self) {
+        for priority in [0, 1] {
+            self.compact_partition(priority);
+        }
+        self.flush_events();
+    }
+
+    fn compact_partition(&mut self, priority: usize) {
+        let address_spaces = self
+            .process_manager
+            .processes_mut()
+            .into_iter()
+            .chain(self.resource_manager.blocked_processes_mut())
+            .chain(self.waiting_to_grow.iter_mut().map(|(process, _)| process))
+            .map(|process| &mut process.address_space)
+            .chain(self.swapper.address_spaces_mut());
+        let Some(compaction) = self.memory_manager.compact(priority, address_spaces) else {
+            return;
+        };
+        if compaction.relocated > 0 {
+            self.events.push(SimEvent::Compacted {
+                area: match priority {
+                    0 => StorageArea::RealTimeMemory,
+                    _ => StorageArea::UserMemory,
+                },
+                moved_blocks: compaction.moved_blocks,
+                relocated: compaction.relocated,
+                cost: self.memory_manager.compaction_cost(compaction.moved_blocks),
+            });
+        }
+    }
+
+    /// Runs a grow or shrink instruction of the running process, blocking it
+    /// when its partition has no room yet.
+    fn resize_memory(&mut self, pid: usize, priority: usize, instruction: MemoryOperation) {
+        let Some(process) = self.process_manager.get_current_process() else {
+            return;
+        };
+        let current = process.address_space.heap().map_or(0, |heap| heap.length);
+        let length = match instruction {
+            MemoryOperation::Grow { blocks } => current + blocks,
+            MemoryOperation::Shrink { blocks } => current.saturating_sub(blocks),
+            _ => current,
+        };
+        match self
+            .memory_manager
+            .resize(priority, &mut process.address_space, length)
+        {
+            Ok(from) => {
+                let to = process
+                    .address_space
+                    .heap()
+                    .cloned()
+                    .unwrap_or(from.clone());
+                self.events.push(SimEvent::MemoryResized {
+                    pid,
+                    priority,
+                    from,
+                    to,
+                });
+            }
+            Err(AllocMemoryError::Unsupported) => {
+                self.events.push(SimEvent::MemoryResizeDenied {
+                    pid,
+                    priority,
+                    blocks: length,
+                });
+            }
+            Err(AllocMemoryError::Unavailable) => {
+                if let Some(process) = self.process_manager.block_current_process(self.timestamp) {
+                    self.events.push(SimEvent::BlockedOnMemory {
+                        pid,
+                        priority,
+                        blocks: length,
+                    });
+                    self.waiting_to_grow.push_back((process, length));
+                }
+            }
+        }
+    }
+
+    /// Retries the growths of the processes blocked on memory, in the order
+    /// they blocked. A growth that could not fit even if every other process
+    /// of the partition terminated is denied and its process goes on.
+    fn retry_growths(&mut self) {
+        for (mut process, length) in std::mem::take(&mut self.waiting_to_grow) {
+            let pid = process.software_context.id;
+            let priority = process.software_context.priority;
+            match self
+                .memory_manager
+                .resize(priority, &mut process.address_space, length)
+            {
+                Ok(from) => {
+                    let to = process
+                        .address_space
+                        .heap()
+                        .cloned()
+                        .unwrap_or(from.clone());
+                    self.events.push(SimEvent::MemoryResized {
+                        pid,
+                        priority,
+                        from,
+                        to,
+                    });
+                }
+                Err(_) if !self.can_grow(&process, length) => {
+                    self.events.push(SimEvent::MemoryResizeDenied {
+                        pid,
+                        priority,
+                        blocks: length,
+                    });
+                }
+                Err(_) => {
+                    self.waiting_to_grow.push_back((process, length));
+                    continue;
+                }
+            }
+            self.process_manager
+                .unblock_process(process, self.timestamp, &mut self.events);
+        }
+    }
+
+    /// Whether the memory freed by the processes that are not waiting to
+    /// grow would make room for `process` to grow to `length`.
+    fn can_grow(&mut self, process: &Process, length: usize) -> bool {
+        let priority = process.software_context.priority;
+        let current = process.address_space.heap().map_or(0, |heap| heap.length);
+        let memory_manager = &self.memory_manager;
+        let reclaimable = self
+            .process_manager
+            .processes_mut()
+            .into_iter()
+            .chain(self.resource_manager.blocked_processes_mut())
+            .filter(|other| (other.software_context.priority == 0) == (priority == 0))
+            .map(|other| memory_manager.resident_blocks(&other.address_space))
+            .chain(
+                self.swapper
+                    .address_spaces_mut()
+                    .into_iter()
+                    .map(|address_space| memory_manager.resident_blocks(address_space)),
+            )
+            .sum::<usize>();
+        memory_manager.free_blocks(priority) + reclaimable >= length.saturating_sub(current)
+    }
+
+    /// Gives back the resources of `process`, readying the processes they
+    /// unblock or queueing them to be swapped in.
+    fn release_resources(&mut self, process: &mut Process) {
+        let unblocked_processes = self
+            .resource_manager
+            .release_resources(process, &mut self.events);
+        for unblocked_process in unblocked_processes {
+            if let Some(unblocked_process) = self.swapper.swap_in(unblocked_process) {
+                self.process_manager.unblock_process(
+                    unblocked_process,
+                    self.timestamp,
+                    &mut self.events,
+                );
+            }
+        }
+    }
+
+    /// Acts on the victims of the deadlock, if any, until it is broken.
+    /// Aborted processes free their memory and resources without completing;
+    /// preempted ones are rolled back and ready again.
+    fn recover_from_deadlock(&mut self) {
+        loop {
+            let victims = self.resource_manager.deadlock_victims();
+            if victims.is_empty() {
+                return;
+            }
+            // Taken out of their queues first so that the resources of one
+            // victim are not handed to another.
+            let victims = victims
+                .into_iter()
+                .filter_map(|pid| self.resource_manager.take_blocked(pid))
+                .collect::<Vec<_>>();
+            for mut victim in victims {
+                let pid = victim.software_context.id;
+                let priority = victim.software_context.priority;
+                if self.resource_manager.recovery() == DeadlockRecovery::Preempt {
+                    self.events.push(SimEvent::RolledBack { pid, priority });
+                    self.release_resources(&mut victim);
+                    victim.roll_back();
+                    if let Some(victim) = self.swapper.swap_in(victim) {
+                        self.process_manager.unblock_process(
+                            victim,
+                            self.timestamp,
+                            &mut self.events,
+                        );
+                    }
+                    continue;
+                }
+                self.events.push(SimEvent::Aborted {
+                    pid,
+                    priority,
+                    address_space: victim.address_space.clone(),
+                });
+                self.resource_manager.withdraw_claim(pid);
+                self.release_resources(&mut victim);
+                self.memory_manager.free(victim.address_space);
+            }
+        }
+    }
+
+    fn sample_free_space(&mut self) {
+        let disk = (StorageArea::Disk, self.file_manager.free_space());
+        for (area, space) in self.memory_manager.free_space().into_iter().chain([disk]) {
+            self.events.push(SimEvent::FreeSpaceSampled { area, space });
+        }
+    }
+
+    fn flush_events(&mut self) {
+        for event in self.events.drain(..) {
+            self.statistics.record(self.timestamp, &event);
+            self.timeline.record(self.timestamp, &event);
+            self.sink.emit(self.timestamp, &event);
+        }
+    }
+
+    fn handle_interruption(&mut self, pid: usize, priority: usize, interruption: Interruption) {
+        let timestamp = self.timestamp;
+        let events = &mut self.events;
+        match interruption {
+            Interruption::None => events.push(SimEvent::CpuInstruction { pid, priority }),
+            Interruption::ProtectionFault(fault) => {
+                events.push(SimEvent::ProtectionFault {
+                    pid,
+                    priority,
+                    segment: fault.segment,
+                    offset: fault.offset,
+                    limit: fault.limit,
+                });
+                self.handle_interruption(pid, priority, Interruption::Terminate);
+            }
+            Interruption::Terminate => {
+                if let Some(mut terminated_process) =
+                    self.process_manager.terminate_current_process(events)
+                {
+                    self.resource_manager.withdraw_claim(pid);
+                    self.release_resources(&mut terminated_process);
+                    self.memory_manager.free(terminated_process.address_space);
+                }
+            }
+            Interruption::AllocResource { resource } => {
+                if let Some(blocked_process) = self.process_manager.block_current_process(timestamp)
+                {
+                    match self
+                        .resource_manager
+                        .request(blocked_process, resource, events)
+                    {
+                        Some(unblocked_process) => self.process_manager.unblock_process(
+                            unblocked_process,
+                            timestamp,
+                            events,
+                        ),
+                        None => self.recover_from_deadlock(),
+                    }
+                }
+            }
+            Interruption::MemoryInterruption {
+                instruction: MemoryOperation::Access { page },
+            } => {
+                let access = self
+                    .process_manager
+                    .get_current_process()
+                    .map(|process| process.access_page(page));
+                match access {
+                    Some(PageAccess::Hit) => events.push(SimEvent::PageHit { pid, page }),
+                    Some(PageAccess::Fault { frame, evicted }) => {
+                        events.push(SimEvent::PageFault {
+                            pid,
+                            page,
+                            frame,
+                            evicted,
+                        })
+                    }
+                    _ => events.push(SimEvent::CpuInstruction { pid, priority }),
+                }
+            }
+            Interruption::MemoryInterruption {
+                instruction: MemoryOperation::SegmentAccess { segment, offset },
+            } => {
+                let translation = self
+                    .process_manager
+                    .get_current_process()
+                    .map(|process| process.translate(segment, offset));
+                match translation {
+                    Some(Ok(Some(address))) => events.push(SimEvent::SegmentAccessed {
+                        pid,
+                        segment,
+                        offset,
+                        address,
+                    }),
+                    Some(Err(fault)) => self.handle_interruption(pid, priority, fault),
+                    _ => events.push(SimEvent::CpuInstruction { pid, priority }),
+                }
+            }
+            Interruption::MemoryInterruption {
+                instruction:
+                    instruction @ (MemoryOperation::Grow { .. } | MemoryOperation::Shrink { .. }),
+            } => self.resize_memory(pid, priority, instruction),
+            Interruption::DiskInterruption { instruction } => {
+                if let Some(mut blocked_process) =
+                    self.process_manager.block_current_process(timestamp)
+                {
+                    match instruction {
+                        DiskOperation::Create {
+                            file_name,
+                            num_blocks,
+                        } => {
+                            self.file_manager.create_file(
+                                &mut blocked_process,
+                                file_name,
+                                num_blocks,
+                                events,
+                            );
+                        }
+                        DiskOperation::Delete { file_name } => {
+                            let _ =
+                                self.file_manager
+                                    .delete_file(&blocked_process, file_name, events);
+                        }
+                    }
+                    self.process_manager
+                        .unblock_process(blocked_process, timestamp, events);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::config::ResourceConfig;
+    use crate::memory::MemoryBackend;
+    use crate::paging::ReplacementPolicy;
+    use crate::parsers::processes_parser::{MemoryOperationDefinition, ResourceRequestDefinition};
+    use crate::resources::{Deadlock, Resource};
+    use crate::segmentation::{SegmentKind, SegmentSizes};
+    use crate::stats::CompactionStats;
+    use crate::structures::fixed_partitions::FixedQueueing;
+
+    use super::*;
+
+    struct SharedSink(Rc<RefCell<Vec<(usize, SimEvent)>>>);
+
+    impl EventSink for SharedSink {
+        fn emit(&mut self, timestamp: usize, event: &SimEvent) {
+            self.0.borrow_mut().push((timestamp, event.clone()));
+        }
+    }
+
+    fn create_definition(id: usize, init_time: usize, priority: usize) -> ProcessDefinition {
+        ProcessDefinition {
+            id,
+            init_time,
+            priority,
+            cpu_time: 2,
+            num_memory_blocks: 8,
+            segments: None,
+            use_printer: false,
+            use_scanner: false,
+            use_modem: false,
+            use_sata: false,
+            memory_operations: vec![],
+            resource_requests: vec![],
+            max_claims: vec![],
+        }
+    }
+
+    fn create_kernel(processes_definitions: Vec<ProcessDefinition>) -> Kernel {
+        Kernel::new(
+            processes_definitions,
+            10,
+            vec![],
+            vec![],
+            &Config::default(),
+            Box::new(vec![]),
+        )
+    }
+
+    #[test]
+    fn run_terminates_every_process() {
+        let mut kernel =
+            create_kernel(vec![create_definition(0, 0, 0), create_definition(1, 1, 1)]);
+        kernel.run();
+        assert!(kernel.is_finished());
+        assert!(kernel.memory_manager.alloc(0, 64).is_ok());
+    }
+
+    #[test]
+    fn paranoid_run_keeps_the_free_lists_consistent() {
+        let mut definitions = vec![create_definition(0, 0, 0), create_definition(1, 0, 1)];
+        definitions[1].memory_operations = vec![
+            MemoryOperationDefinition::Grow { blocks: 4 },
+            MemoryOperationDefinition::Shrink { blocks: 8 },
+        ];
+        let mut kernel = Kernel::new(
+            definitions,
+            10,
+            vec![(
+                'A',
+                Segment {
+                    offset: 0,
+                    length: 2,
+                },
+            )],
+            vec![],
+            &Config {
+                paranoid: true,
+                ..Config::default()
+            },
+            Box::new(vec![]),
+        );
+        kernel.run();
+        assert!(kernel.is_finished());
+    }
+
+    #[test]
+    fn run_until_stops_at_timestamp() {
+        let mut kernel = create_kernel(vec![create_definition(0, 0, 1)]);
+        kernel.run_until(1);
+        assert_eq!(kernel.timestamp(), 1);
+        assert!(!kernel.is_finished());
+    }
+
+    #[test]
+    fn step_advances_one_tick() {
+        let mut kernel = create_kernel(vec![create_definition(0, 0, 0)]);
+        kernel.step();
+        kernel.step();
+        assert_eq!(kernel.timestamp(), 2);
+    }
+
+    #[test]
+    fn events_are_stamped_with_their_tick() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut kernel = Kernel::new(
+            vec![create_definition(0, 1, 0)],
+            10,
+            vec![],
+            vec![],
+            &Config::default(),
+            Box::new(SharedSink(events.clone())),
+        );
+        kernel.run();
+        let events = events.borrow();
+        assert!(matches!(events[0], (0, SimEvent::FileSystemLoaded { .. })));
+        assert!(events.contains(&(
+            1,
+            SimEvent::Dispatched {
+                pid: 0,
+                priority: 0
+            }
+        )));
+        assert!(events.contains(&(
+            1,
+            SimEvent::CpuInstruction {
+                pid: 0,
+                priority: 0
+            }
+        )));
+        assert!(matches!(
+            events
+                .iter()
+                .rev()
+                .find(|(_, event)| !matches!(event, SimEvent::FreeSpaceSampled { .. })),
+            Some((3, SimEvent::Terminated { pid: 0, .. }))
+        ));
+    }
+
+    /// Two processes taking a scanner and a modem in opposite orders. The
+    /// page references keep each process from taking both resources before
+    /// the other one runs.
+    fn create_deadlocking_definitions() -> Vec<ProcessDefinition> {
+        let mut first = create_definition(1, 0, 1);
+        first.use_scanner = true;
+        first.memory_operations = vec![MemoryOperationDefinition::Access { page: 0 }];
+        first.resource_requests = vec![ResourceRequestDefinition {
+            resource: Resource::Modem,
+            after: 1,
+        }];
+        let mut second = create_definition(2, 0, 1);
+        second.use_modem = true;
+        second.memory_operations = vec![MemoryOperationDefinition::Access { page: 0 }];
+        second.resource_requests = vec![ResourceRequestDefinition {
+            resource: Resource::Scanner,
+            after: 1,
+        }];
+        vec![first, second]
+    }
+
+    #[test]
+    fn detects_processes_requesting_resources_in_opposite_orders() {
+        let mut kernel = create_kernel(create_deadlocking_definitions());
+        kernel.run();
+        assert_eq!(kernel.statistics().deadlocks(), &[(5, vec![1, 2])]);
+        assert_eq!(
+            kernel.resource_manager.detect_deadlock(),
+            Some(Deadlock {
+                pids: vec![1, 2],
+                resources: vec![Resource::Scanner, Resource::Modem],
+            })
+        );
+    }
+
+    #[test]
+    fn stops_when_deadlocked_processes_hold_the_memory_a_waiter_needs() {
+        let mut definitions = create_deadlocking_definitions();
+        for definition in &mut definitions {
+            definition.num_memory_blocks = 400;
+        }
+        let mut waiter = create_definition(3, 3, 1);
+        waiter.num_memory_blocks = 400;
+        definitions.push(waiter);
+        let mut kernel = create_kernel(definitions);
+        kernel.run();
+        assert!(kernel.is_finished());
+        assert_eq!(kernel.statistics().deadlocks(), &[(5, vec![1, 2])]);
+        assert_eq!(kernel.statistics().stranded(), Some(&(7, vec![1, 2, 3])));
+    }
+
+    #[test]
+    fn recovers_from_deadlocks() {
+        let run = |deadlock_recovery| {
+            let mut kernel = Kernel::new(
+                create_deadlocking_definitions(),
+                10,
+                vec![],
+                vec![],
+                &Config {
+                    resources: ResourceConfig {
+                        deadlock_recovery,
+                        ..ResourceConfig::default()
+                    },
+                    ..Config::default()
+                },
+                Box::new(vec![]),
+            );
+            kernel.run();
+            assert!(kernel.is_finished());
+            assert_eq!(kernel.resource_manager.detect_deadlock(), None);
+            assert!(kernel.memory_manager.alloc(1, 960).is_ok());
+            [1, 2].map(|pid| {
+                let process = kernel.statistics().process(pid).unwrap();
+                (process.aborted, process.rollbacks)
+            })
+        };
+        assert_eq!(run(DeadlockRecovery::AbortAll), [(true, 0), (true, 0)]);
+        assert_eq!(run(DeadlockRecovery::AbortOne), [(false, 0), (true, 0)]);
+        assert_eq!(run(DeadlockRecovery::Preempt), [(false, 0), (false, 1)]);
+    }
+
+    #[test]
+    fn rolls_back_only_the_resource_requests() {
+        let mut definitions = create_deadlocking_definitions();
+        definitions[1].memory_operations = vec![
+            MemoryOperationDefinition::Grow { blocks: 4 },
+            MemoryOperationDefinition::Access { page: 0 },
+        ];
+        definitions[1].resource_requests[0].after = 2;
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut kernel = Kernel::new(
+            definitions,
+            10,
+            vec![],
+            vec![DiskOperationDefinition::Create {
+                process_id: 2,
+                file_name: 'A',
+                file_size: 2,
+            }],
+            &Config {
+                resources: ResourceConfig {
+                    deadlock_recovery: DeadlockRecovery::Preempt,
+                    ..ResourceConfig::default()
+                },
+                ..Config::default()
+            },
+            Box::new(SharedSink(events.clone())),
+        );
+        kernel.run();
+        assert_eq!(kernel.statistics().process(2).unwrap().rollbacks, 1);
+        let events = events.borrow();
+        let count = |matches: fn(&SimEvent) -> bool| {
+            events.iter().filter(|(_, event)| matches(event)).count()
+        };
+        assert_eq!(
+            count(|event| matches!(event, SimEvent::FileCreated { .. })),
+            1
+        );
+        assert_eq!(
+            count(|event| matches!(event, SimEvent::MemoryResized { .. })),
+            1
+        );
+        let grants = events
+            .iter()
+            .filter_map(|(_, event)| match event {
+                SimEvent::ResourceGranted {
+                    pid: 2, resource, ..
+                }
+                | SimEvent::Unblocked {
+                    pid: 2, resource, ..
+                } => Some(*resource),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            grants,
+            [Resource::Modem, Resource::Modem, Resource::Scanner]
+        );
+        assert!(kernel.memory_manager.alloc(1, 960).is_ok());
+    }
+
+    #[test]
+    fn avoids_deadlocks_with_the_bankers_algorithm() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut kernel = Kernel::new(
+            create_deadlocking_definitions(),
+            10,
+            vec![],
+            vec![],
+            &Config {
+                resources: ResourceConfig {
+                    deadlock_avoidance: true,
+                    ..ResourceConfig::default()
+                },
+                verbose: true,
+                ..Config::default()
+            },
+            Box::new(SharedSink(events.clone())),
+        );
+        kernel.run();
+        assert!(kernel.statistics().deadlocks().is_empty());
+        assert!(kernel
+            .statistics()
+            .process(2)
+            .unwrap()
+            .completion_time
+            .is_some());
+        let events = events.borrow();
+        let sequences = events
+            .iter()
+            .filter_map(|(_, event)| match event {
+                SimEvent::SafeSequence { pid, sequence, .. } => Some((*pid, sequence.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(sequences[..2], [(1, vec![1, 2]), (1, vec![1, 2])]);
+    }
+
+    #[test]
+    fn collects_statistics() {
+        let mut kernel = create_kernel(vec![create_definition(0, 1, 1)]);
+        kernel.run();
+        let process = kernel.statistics().process(0).unwrap();
+        assert_eq!(process.response(), Some(0));
+        assert_eq!(process.cpu_ticks, 3);
+        assert_eq!(process.turnaround(), Some(3));
+        assert_eq!(kernel.statistics().total_ticks(), kernel.timestamp());
+    }
+
+    #[test]
+    fn counts_page_faults_of_paged_processes() {
+        let page_faults = |replacement| {
+            let mut definition = create_definition(0, 0, 1);
+            definition.num_memory_blocks = 64;
+            definition.memory_operations = [1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5]
+                .map(|page| MemoryOperationDefinition::Access { page })
+                .into();
+            let mut config = Config::default();
+            config.memory.backend = MemoryBackend::Paged;
+            config.memory.page_size = 8;
+            config.memory.frames_per_process = 3;
+            config.memory.replacement = replacement;
+            let mut kernel = Kernel::new(
+                vec![definition],
+                10,
+                vec![],
+                vec![],
+                &config,
+                Box::new(vec![]),
+            );
+            kernel.run();
+            assert!(kernel.memory_manager.alloc(1, 960).is_ok());
+            kernel.statistics().process(0).unwrap().page_faults
+        };
+        assert_eq!(page_faults(ReplacementPolicy::Fifo), 9);
+        assert_eq!(page_faults(ReplacementPolicy::Optimal), 7);
+    }
+
+    #[test]
+    fn swaps_out_blocked_processes_to_admit_waiting_ones() {
+        let definition = |id, init_time, cpu_time, num_memory_blocks| ProcessDefinition {
+            cpu_time,
+            num_memory_blocks,
+            use_scanner: id < 3,
+            ..create_definition(id, init_time, 1)
+        };
+        let mut config = Config::default();
+        config.memory.user_partition_size = 100;
+        config.swap.enabled = true;
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut kernel = Kernel::new(
+            vec![
+                definition(0, 0, 8, 10),
+                definition(1, 0, 2, 40),
+                definition(2, 0, 2, 40),
+                definition(3, 1, 2, 30),
+            ],
+            10,
+            vec![],
+            vec![],
+            &config,
+            Box::new(SharedSink(events.clone())),
+        );
+        kernel.run();
+        let events = events.borrow();
+        let tick_of = |wanted: &dyn Fn(&SimEvent) -> bool| {
+            events
+                .iter()
+                .find(|(_, event)| wanted(event))
+                .map(|(timestamp, _)| *timestamp)
+        };
+        let swapped_out = tick_of(&|event| matches!(event, SimEvent::SwappedOut { pid: 2, .. }));
+        let created = tick_of(&|event| matches!(event, SimEvent::ProcessCreated { pid: 3, .. }));
+        let swapped_in = tick_of(&|event| matches!(event, SimEvent::SwappedIn { pid: 2, .. }));
+        assert!(swapped_out.unwrap() <= created.unwrap());
+        assert!(created < swapped_in);
+        let statistics = kernel.statistics();
+        assert_eq!(statistics.process(1).unwrap().swaps, 0);
+        assert_eq!(statistics.process(2).unwrap().swaps, 1);
+        assert!(statistics.process(2).unwrap().completion_time.is_some());
+        assert!(kernel.memory_manager.alloc(1, 100).is_ok());
+    }
+
+    #[test]
+    fn compacts_memory_when_a_process_fits_only_in_the_free_blocks() {
+        let definition = |id, init_time, cpu_time, num_memory_blocks| ProcessDefinition {
+            cpu_time,
+            num_memory_blocks,
+            ..create_definition(id, init_time, 1)
+        };
+        let mut config = Config::default();
+        config.memory.user_partition_size = 30;
+        config.memory.compaction = true;
+        config.memory.compaction_blocks_per_tick = 4;
+        let mut kernel = Kernel::new(
+            vec![
+                definition(0, 0, 1, 10),
+                definition(1, 0, 10, 10),
+                definition(2, 3, 1, 20),
+            ],
+            10,
+            vec![],
+            vec![],
+            &config,
+            Box::new(vec![]),
+        );
+        kernel.run();
+        let statistics = kernel.statistics();
+        assert_eq!(statistics.process(2).unwrap().memory_wait(), 0);
+        assert_eq!(
+            *statistics.compactions(),
+            CompactionStats {
+                count: 1,
+                moved_blocks: 10,
+                relocated: 1,
+                cost: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn compacts_memory_on_demand() {
+        let mut long_process = create_definition(1, 0, 1);
+        long_process.cpu_time = 10;
+        let mut kernel = create_kernel(vec![create_definition(0, 0, 1), long_process]);
+        kernel.step();
+        kernel.compact_memory();
+        assert_eq!(kernel.statistics().compactions().count, 0);
+        while kernel
+            .statistics()
+            .process(0)
+            .unwrap()
+            .completion_time
+            .is_none()
+        {
+            kernel.step();
+        }
+        kernel.compact_memory();
+        assert_eq!(kernel.statistics().compactions().moved_blocks, 8);
+        assert!(kernel.memory_manager.alloc(1, 952).is_ok());
+    }
+
+    #[test]
+    fn fixed_partition_queues_decide_where_processes_wait() {
+        let memory_wait = |fixed_queueing| {
+            let mut long_process = create_definition(0, 0, 1);
+            long_process.cpu_time = 5;
+            let mut config = Config::default();
+            config.memory.backend = MemoryBackend::Fixed;
+            config.memory.user_fixed_partitions = vec![20, 40];
+            config.memory.fixed_queueing = fixed_queueing;
+            let mut kernel = Kernel::new(
+                vec![long_process, create_definition(1, 0, 1)],
+                10,
+                vec![],
+                vec![],
+                &config,
+                Box::new(vec![]),
+            );
+            kernel.run();
+            let [_, (_, user_space)] = kernel.memory_manager.free_space();
+            assert_eq!(user_space.free_blocks, 60);
+            kernel.statistics().process(1).unwrap().memory_wait()
+        };
+        assert_eq!(memory_wait(FixedQueueing::Shared), 0);
+        assert_eq!(memory_wait(FixedQueueing::PerPartition), 6);
+    }
+
+    #[test]
+    fn terminates_processes_accessing_out_of_their_segments() {
+        let mut definition = create_definition(0, 0, 1);
+        definition.num_memory_blocks = 14;
+        definition.segments = Some(SegmentSizes {
+            code: 8,
+            data: 4,
+            stack: 2,
+        });
+        definition.memory_operations = vec![
+            MemoryOperationDefinition::SegmentAccess {
+                segment: SegmentKind::Data,
+                offset: 3,
+            },
+            MemoryOperationDefinition::SegmentAccess {
+                segment: SegmentKind::Data,
+                offset: 4,
+            },
+            MemoryOperationDefinition::SegmentAccess {
+                segment: SegmentKind::Stack,
+                offset: 0,
+            },
+        ];
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut kernel = Kernel::new(
+            vec![definition],
+            10,
+            vec![],
+            vec![],
+            &Config::default(),
+            Box::new(SharedSink(events.clone())),
+        );
+        kernel.run();
+        let events = events
+            .borrow()
+            .iter()
+            .map(|(_, event)| event.clone())
+            .filter(|event| {
+                matches!(
+                    event,
+                    SimEvent::SegmentAccessed { .. }
+                        | SimEvent::ProtectionFault { .. }
+                        | SimEvent::Terminated { .. }
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[0],
+            SimEvent::SegmentAccessed {
+                pid: 0,
+                segment: SegmentKind::Data,
+                offset: 3,
+                address: 64 + 8 + 3,
+            }
+        );
+        assert_eq!(
+            events[1],
+            SimEvent::ProtectionFault {
+                pid: 0,
+                priority: 2,
+                segment: SegmentKind::Data,
+                offset: 4,
+                limit: 4,
+            }
+        );
+        assert!(matches!(events[2], SimEvent::Terminated { pid: 0, .. }));
+        let process = kernel.statistics().process(0).unwrap();
+        assert_eq!(process.segment_accesses, 2);
+        assert!(process.protection_fault.is_some());
+        assert!(kernel.memory_manager.alloc(1, 960).is_ok());
+    }
+
+    #[test]
+    fn blocks_growing_processes_until_their_partition_has_room() {
+        let definition = |id, cpu_time, memory_operations| ProcessDefinition {
+            cpu_time,
+            num_memory_blocks: 20,
+            memory_operations,
+            ..create_definition(id, 0, 1)
+        };
+        let mut config = Config::default();
+        config.memory.user_partition_size = 50;
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut kernel = Kernel::new(
+            vec![
+                definition(0, 3, vec![]),
+                definition(
+                    1,
+                    1,
+                    vec![
+                        MemoryOperationDefinition::Grow { blocks: 10 },
+                        MemoryOperationDefinition::Grow { blocks: 5 },
+                    ],
+                ),
+            ],
+            10,
+            vec![],
+            vec![],
+            &config,
+            Box::new(SharedSink(events.clone())),
+        );
+        kernel.run();
+        let events = events.borrow();
+        let tick_of = |wanted: &dyn Fn(&SimEvent) -> bool| {
+            events
+                .iter()
+                .find(|(_, event)| wanted(event))
+                .map(|(timestamp, _)| *timestamp)
+                .unwrap()
+        };
+        let resized = events
+            .iter()
+            .filter_map(|(_, event)| match event {
+                SimEvent::MemoryResized { from, to, .. } => Some((from.offset, to.clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            resized,
+            vec![
+                (
+                    84,
+                    Segment {
+                        offset: 84,
+                        length: 30
+                    }
+                ),
+                (
+                    84,
+                    Segment {
+                        offset: 64,
+                        length: 35
+                    }
+                ),
+            ]
+        );
+        let blocked = tick_of(&|event| matches!(event, SimEvent::BlockedOnMemory { pid: 1, .. }));
+        let terminated = tick_of(&|event| matches!(event, SimEvent::Terminated { pid: 0, .. }));
+        let moved = tick_of(
+            &|event| matches!(event, SimEvent::MemoryResized { to, .. } if to.offset == 64),
+        );
+        assert!(blocked < terminated && terminated < moved);
+        assert!(kernel.memory_manager.alloc(1, 50).is_ok());
+    }
+
+    #[test]
+    fn denies_growths_no_process_can_make_room_for() {
+        let definition = |id| ProcessDefinition {
+            num_memory_blocks: 25,
+            memory_operations: vec![MemoryOperationDefinition::Grow { blocks: 10 }],
+            ..create_definition(id, 0, 1)
+        };
+        let mut config = Config::default();
+        config.memory.user_partition_size = 50;
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut kernel = Kernel::new(
+            vec![definition(0), definition(1)],
+            10,
+            vec![],
+            vec![],
+            &config,
+            Box::new(SharedSink(events.clone())),
+        );
+        kernel.run();
+        let denied = events
+            .borrow()
+            .iter()
+            .filter(|(_, event)| matches!(event, SimEvent::MemoryResizeDenied { .. }))
+            .count();
+        assert_eq!(denied, 1);
+        assert!(kernel.memory_manager.alloc(1, 50).is_ok());
+    }
+}
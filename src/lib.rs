@@ -0,0 +1,28 @@
+pub mod config;
+pub mod dispatcher;
+pub mod events;
+pub mod files;
+pub mod gantt;
+pub mod kernel;
+pub mod memory;
+pub mod paging;
+pub mod process;
+pub mod queues;
+pub mod resources;
+pub mod scheduler;
+pub mod segmentation;
+pub mod stats;
+pub mod swapper;
+pub mod validation;
+pub mod structures {
+    pub mod buddy_allocator;
+    pub mod fixed_partitions;
+    pub mod frame_pool;
+    pub mod hole_tree;
+    pub mod segment_list;
+}
+pub mod parsers {
+    pub mod files_parser;
+    pub mod parse_error;
+    pub mod processes_parser;
+}
@@ -1,107 +1,229 @@
-mod files;
-mod memory;
-mod process;
-mod queues;
-mod structures {
-    pub mod segment_list;
+use so_project::config::Config;
+use so_project::events::{EventSink, JsonlRenderer, TextRenderer};
+use so_project::kernel::Kernel;
+use so_project::parsers;
+use so_project::scheduler::SchedulerKind;
+use so_project::validation;
+
+enum Format {
+    Text,
+    Jsonl,
+}
+
+struct Options {
+    processes_path: String,
+    files_path: String,
+    format: Format,
+    gantt: bool,
+    gantt_svg_path: Option<String>,
+    paranoid: bool,
+    verbose: bool,
+    config_path: Option<String>,
+    // Command line settings override the ones of the configuration file.
+    scheduler: Option<SchedulerKind>,
+    aging_threshold: Option<usize>,
+    boost_interval: Option<usize>,
+    quanta: Option<Vec<usize>>,
 }
-mod parsers {
-    pub mod files_parser;
-    pub mod processes_parser;
+
+fn parse_options(argv: &[String]) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut format = Format::Text;
+    let mut gantt = false;
+    let mut gantt_svg_path = None;
+    let mut paranoid = false;
+    let mut verbose = false;
+    let mut config_path = None;
+    let mut scheduler = None;
+    let mut aging_threshold = None;
+    let mut boost_interval = None;
+    let mut quanta = None;
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = match args.next().map(String::as_str) {
+                    Some("text") => Format::Text,
+                    Some("jsonl") => Format::Jsonl,
+                    Some(other) => return Err(format!("unknown format `{}`", other)),
+                    None => return Err(String::from("`--format` expects a value")),
+                }
+            }
+            "--config" => match args.next() {
+                Some(path) => config_path = Some(path.clone()),
+                None => return Err(String::from("`--config` expects a path")),
+            },
+            "--scheduler" => match args.next() {
+                Some(name) => scheduler = Some(name.parse()?),
+                None => return Err(String::from("`--scheduler` expects a name")),
+            },
+            "--aging" => aging_threshold = Some(parse_ticks("--aging", args.next())?),
+            "--boost" => boost_interval = Some(parse_ticks("--boost", args.next())?),
+            "--quanta" => quanta = Some(parse_quanta(args.next())?),
+            "--gantt" => gantt = true,
+            "--gantt-svg" => match args.next() {
+                Some(path) => gantt_svg_path = Some(path.clone()),
+                None => return Err(String::from("`--gantt-svg` expects a path")),
+            },
+            "--paranoid" => paranoid = true,
+            "--verbose" => verbose = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            path => positional.push(path.to_string()),
+        }
+    }
+    let mut positional = positional.into_iter();
+    Ok(Options {
+        processes_path: positional
+            .next()
+            .unwrap_or_else(|| String::from("input/processes.txt")),
+        files_path: positional
+            .next()
+            .unwrap_or_else(|| String::from("input/files.txt")),
+        format,
+        gantt,
+        gantt_svg_path,
+        paranoid,
+        verbose,
+        config_path,
+        scheduler,
+        aging_threshold,
+        boost_interval,
+        quanta,
+    })
 }
-mod dispatcher;
 
-use crate::dispatcher::Dispatcher;
-use crate::files::FileManager;
+fn load_config(options: &Options) -> Result<Config, String> {
+    let mut config = match &options.config_path {
+        Some(path) => Config::load(path).map_err(|error| format!("{}: {}", path, error))?,
+        None => Config::default(),
+    };
+    if let Some(scheduler) = options.scheduler {
+        config.scheduler.policy = scheduler;
+    }
+    if let Some(threshold) = options.aging_threshold {
+        config.scheduler.aging.threshold = Some(threshold);
+    }
+    if let Some(interval) = options.boost_interval {
+        config.scheduler.aging.boost_interval = Some(interval);
+    }
+    if let Some(quanta) = &options.quanta {
+        config.scheduler.user_queues = quanta.len();
+        config.scheduler.quanta = quanta.clone();
+    }
+    config.paranoid |= options.paranoid;
+    config.verbose |= options.verbose;
+    config.check()?;
+    Ok(config)
+}
 
-use crate::memory::MemoryManager;
-use crate::process::{DiskOperation, Interruption};
-use crate::queues::ProcessManager;
-use crate::resources::ResourceManager;
+fn parse_quanta(value: Option<&String>) -> Result<Vec<usize>, String> {
+    let error = || String::from("`--quanta` expects a comma separated list of positive ticks");
+    let quanta = value
+        .ok_or_else(error)?
+        .split(',')
+        .map(|quantum| match quantum.trim().parse::<usize>() {
+            Ok(quantum) if quantum > 0 => Ok(quantum),
+            _ => Err(error()),
+        })
+        .collect::<Result<Vec<usize>, String>>()?;
+    Ok(quanta)
+}
 
-mod resources;
+fn parse_ticks(flag: &str, value: Option<&String>) -> Result<usize, String> {
+    match value.map(|value| value.parse::<usize>()) {
+        Some(Ok(ticks)) if ticks > 0 => Ok(ticks),
+        _ => Err(format!("`{}` expects a positive number of ticks", flag)),
+    }
+}
 
 fn main() {
     let argv = std::env::args().collect::<Vec<String>>();
-    let (default_processes_path, default_files_path) = (
-        String::from("input/processes.txt"),
-        String::from("input/files.txt"),
-    );
-    let processes_path = argv.get(1).unwrap_or(&default_processes_path);
-    let files_path = argv.get(2).unwrap_or(&default_files_path);
-    println!("Processes path: {}", processes_path);
-    println!("Files path: {}", files_path);
-
-    // Parse processes
-    let processes_definitions = parsers::processes_parser::parse(processes_path);
-    // Parse files
-    let (num_blocks, alloc_disk_blocks, disk_operation_definitions) =
-        parsers::files_parser::parse(files_path);
+    let options = match parse_options(&argv) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            eprintln!(
+                "usage: so-project [--config path] [--format text|jsonl] [--scheduler feedback|fcfs|sjf|srtf|rr] \
+                 [--quanta q1,q2,q3] [--aging ticks] [--boost ticks] \
+                 [--gantt] [--gantt-svg path] [--paranoid] [--verbose] \
+                 [processes.txt] [files.txt]"
+            );
+            std::process::exit(2);
+        }
+    };
+    let config = match load_config(&options) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            std::process::exit(1);
+        }
+    };
 
-    let mut memory_manager = MemoryManager::new();
-    let mut file_manager = FileManager::new(num_blocks, alloc_disk_blocks);
-    let mut dispatcher = Dispatcher::new(processes_definitions, disk_operation_definitions);
-    let mut process_manager = ProcessManager::new();
-    let mut resource_manager = ResourceManager::new();
-    let mut timestamp = 0;
-    while dispatcher.has_more_processes(timestamp) || process_manager.has_more_processes() {
-        let new_processes = dispatcher.generate_new_processes(&mut memory_manager, timestamp);
-        for process in new_processes {
-            process_manager.add_process(process, timestamp);
+    let sink: Box<dyn EventSink> = match options.format {
+        Format::Text => {
+            println!("Processes path: {}", options.processes_path);
+            println!("Files path: {}", options.files_path);
+            Box::new(TextRenderer::new())
         }
+        Format::Jsonl => Box::new(JsonlRenderer::new(std::io::stdout())),
+    };
 
-        if let Some(current_process) = process_manager.get_current_process() {
-            match current_process.on_tick() {
-                Interruption::None => {
-                    println!(
-                        "Process {} CPU instruction\n",
-                        current_process.software_context.id,
-                    )
+    let processes = parsers::processes_parser::parse(&options.processes_path);
+    let files = parsers::files_parser::parse(&options.files_path);
+    let (processes_definitions, (num_blocks, alloc_disk_blocks, disk_operation_definitions)) =
+        match (processes, files) {
+            (Ok(processes), Ok(files)) => (processes, files),
+            (processes, files) => {
+                let errors = [processes.err(), files.err()]
+                    .into_iter()
+                    .flatten()
+                    .flatten();
+                for error in errors {
+                    eprintln!("{}\n", error);
                 }
-                Interruption::Terminate => {
-                    let teminated_process = process_manager.terminate_current_process();
-                    if let Some(mut terminated_process) = teminated_process {
-                        let unblocked_processes =
-                            resource_manager.release_resources(&mut terminated_process);
-                        for unblocked_process in unblocked_processes {
-                            process_manager.add_process(unblocked_process, timestamp);
-                        }
-                        memory_manager.free(terminated_process.address_space);
-                    }
-                }
-                Interruption::AllocResource { resource } => {
-                    if let Some(blocked_process) = process_manager.block_current_process() {
-                        if let Some(unblocked_process) =
-                            resource_manager.request(blocked_process, resource)
-                        {
-                            process_manager.add_process(unblocked_process, timestamp);
-                        }
-                    }
-                }
-                Interruption::DiskInterruption { instruction } => {
-                    if let Some(mut blocked_process) = process_manager.block_current_process() {
-                        match instruction {
-                            DiskOperation::Create {
-                                file_name,
-                                num_blocks,
-                            } => {
-                                file_manager.create_file(
-                                    &mut blocked_process,
-                                    file_name,
-                                    num_blocks,
-                                );
-                                process_manager.add_process(blocked_process, timestamp);
-                            }
-                            DiskOperation::Delete { file_name } => {
-                                let _ = file_manager.delete_file(&mut blocked_process, file_name);
-                                process_manager.add_process(blocked_process, timestamp);
-                            }
-                        }
-                    }
-                },
+                std::process::exit(1);
             }
+        };
+
+    let diagnostics = validation::validate(
+        &config,
+        &processes_definitions,
+        num_blocks,
+        &alloc_disk_blocks,
+        &disk_operation_definitions,
+    );
+    for diagnostic in &diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+    if validation::has_errors(&diagnostics) {
+        std::process::exit(1);
+    }
+
+    let mut kernel = Kernel::new(
+        processes_definitions,
+        num_blocks,
+        alloc_disk_blocks,
+        disk_operation_definitions,
+        &config,
+        sink,
+    );
+    kernel.run();
+
+    let mut report = format!("Scheduling statistics\n{}", kernel.statistics().render());
+    if options.gantt {
+        report.push_str(&format!(
+            "\nGantt chart\n{}",
+            kernel.timeline().render_ascii()
+        ));
+    }
+    match options.format {
+        Format::Text => println!("\n{}", report),
+        Format::Jsonl => eprintln!("{}", report),
+    }
+    if let Some(path) = options.gantt_svg_path {
+        if let Err(error) = std::fs::write(&path, kernel.timeline().render_svg()) {
+            eprintln!("error: could not write {}: {}", path, error);
+            std::process::exit(1);
         }
-        timestamp += 1;
-        process_manager.on_tick(timestamp);
     }
 }
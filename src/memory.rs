@@ -1,12 +1,275 @@
-use crate::structures::segment_list::{Segment, SegmentList};
+use std::str::FromStr;
 
-pub struct MemoryManager {
-    real_time_partition: SegmentList,
-    user_partition: SegmentList,
+use crate::config::MemoryConfig;
+use crate::events::StorageArea;
+use crate::paging::PageTable;
+use crate::segmentation::{ProtectionFault, SegmentKind, SegmentSizes, SegmentTable};
+use crate::structures::buddy_allocator::BuddyAllocator;
+use crate::structures::fixed_partitions::FixedPartitions;
+use crate::structures::frame_pool::FramePool;
+use crate::structures::segment_list::{Compaction, FreeSpace, Segment, SegmentList};
+
+/// How each memory partition keeps track of its free space.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum MemoryBackend {
+    /// Variable-size segments placed by an `AllocationPolicy`.
+    #[default]
+    Segments,
+    /// Power-of-two blocks split and coalesced by a buddy system.
+    Buddy,
+    /// Fixed-size frames holding the pages of paged address spaces.
+    Paged,
+    /// Partitions of configured sizes, each holding one process (MFT).
+    Fixed,
+}
+
+impl FromStr for MemoryBackend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "segments" => Ok(MemoryBackend::Segments),
+            "buddy" => Ok(MemoryBackend::Buddy),
+            "paged" => Ok(MemoryBackend::Paged),
+            "fixed" => Ok(MemoryBackend::Fixed),
+            _ => Err(format!(
+                "unknown memory backend `{}`, expected one of segments, buddy, paged, fixed",
+                name
+            )),
+        }
+    }
+}
+
+/// The memory a process asks for.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MemoryRequest {
+    Blocks(usize),
+    Segments(SegmentSizes),
+}
+
+impl MemoryRequest {
+    pub fn blocks(&self) -> usize {
+        match self {
+            MemoryRequest::Blocks(blocks) => *blocks,
+            MemoryRequest::Segments(sizes) => sizes.total(),
+        }
+    }
+}
+
+/// The memory given to a process.
+#[derive(Debug, PartialEq, Clone)]
+pub enum AddressSpace {
+    Contiguous(Segment),
+    /// Code, data and stack segments placed independently.
+    Segmented(SegmentTable),
+    Paged(PageTable),
+    /// Written out to the swap area, allocated again from the request once
+    /// brought back.
+    Swapped(MemoryRequest),
+}
+
+impl AddressSpace {
+    /// Offset of the first block or frame, used to find the owning partition.
+    fn first_offset(&self) -> Option<usize> {
+        match self {
+            AddressSpace::Contiguous(segment) => Some(segment.offset),
+            AddressSpace::Segmented(segment_table) => segment_table
+                .entries()
+                .first()
+                .map(|(_, segment)| segment.offset),
+            AddressSpace::Paged(page_table) => page_table.frames().first().copied(),
+            AddressSpace::Swapped(_) => None,
+        }
+    }
+
+    /// Physical block of `offset` within `segment`. A contiguous address
+    /// space is a single segment holding code, data and stack; paged and
+    /// swapped ones are not translated and give `None`.
+    pub fn translate(
+        &self,
+        segment: SegmentKind,
+        offset: usize,
+    ) -> Result<Option<usize>, ProtectionFault> {
+        match self {
+            AddressSpace::Contiguous(base) if offset < base.length => {
+                Ok(Some(base.offset + offset))
+            }
+            AddressSpace::Contiguous(base) => Err(ProtectionFault {
+                segment,
+                offset,
+                limit: base.length,
+            }),
+            AddressSpace::Segmented(segment_table) => {
+                segment_table.translate(segment, offset).map(Some)
+            }
+            AddressSpace::Paged(_) | AddressSpace::Swapped(_) => Ok(None),
+        }
+    }
+
+    /// The segment growing or shrinking the memory of a process moves the
+    /// end of: the whole contiguous segment, or the data segment.
+    pub fn heap(&self) -> Option<&Segment> {
+        match self {
+            AddressSpace::Contiguous(segment) => Some(segment),
+            AddressSpace::Segmented(segment_table) => segment_table.segment(SegmentKind::Data),
+            AddressSpace::Paged(_) | AddressSpace::Swapped(_) => None,
+        }
+    }
+
+    pub fn heap_mut(&mut self) -> Option<&mut Segment> {
+        match self {
+            AddressSpace::Contiguous(segment) => Some(segment),
+            AddressSpace::Segmented(segment_table) => segment_table.segment_mut(SegmentKind::Data),
+            AddressSpace::Paged(_) | AddressSpace::Swapped(_) => None,
+        }
+    }
 }
 
-const REAL_TIME_PARTITION_SIZE: usize = 64;
-const USER_PARTITION_SIZE: usize = 960;
+enum Partition {
+    Segments(SegmentList),
+    Buddy(BuddyAllocator),
+    Frames(FramePool),
+    Fixed(FixedPartitions),
+}
+
+impl Partition {
+    /// `sizes` are the fixed partitions of the region with the `Fixed` backend.
+    fn new(config: &MemoryConfig, offset: usize, length: usize, sizes: &[usize]) -> Self {
+        match config.backend {
+            MemoryBackend::Segments => Partition::Segments(SegmentList::with_policy(
+                vec![Segment { offset, length }],
+                config.allocation,
+            )),
+            MemoryBackend::Buddy => Partition::Buddy(BuddyAllocator::new(offset, length)),
+            MemoryBackend::Paged => {
+                Partition::Frames(FramePool::new(offset, length, config.page_size))
+            }
+            MemoryBackend::Fixed => {
+                Partition::Fixed(FixedPartitions::new(offset, sizes, config.fixed_queueing))
+            }
+        }
+    }
+
+    fn alloc(&mut self, length: usize, config: &MemoryConfig) -> Option<AddressSpace> {
+        match self {
+            Partition::Segments(segments) => segments.alloc(length).map(AddressSpace::Contiguous),
+            Partition::Buddy(buddy) => buddy.alloc(length).map(AddressSpace::Contiguous),
+            Partition::Fixed(partitions) => partitions.alloc(length).map(AddressSpace::Contiguous),
+            Partition::Frames(frame_pool) => {
+                let pages = length.div_ceil(config.page_size);
+                let frames = frame_pool.alloc(pages.min(config.frames_per_process))?;
+                Some(AddressSpace::Paged(PageTable::new(
+                    pages,
+                    frames,
+                    config.replacement,
+                )))
+            }
+        }
+    }
+
+    fn free(&mut self, address_space: AddressSpace) {
+        match (self, address_space) {
+            (Partition::Segments(segments), AddressSpace::Contiguous(segment)) => {
+                segments.free(segment)
+            }
+            (Partition::Buddy(buddy), AddressSpace::Contiguous(segment)) => buddy.free(segment),
+            (Partition::Fixed(partitions), AddressSpace::Contiguous(segment)) => {
+                partitions.free(segment)
+            }
+            (Partition::Frames(frame_pool), AddressSpace::Paged(page_table)) => {
+                frame_pool.free(&page_table.frames())
+            }
+            (partition, AddressSpace::Segmented(segment_table)) => {
+                for segment in segment_table.into_segments() {
+                    partition.free(AddressSpace::Contiguous(segment));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Gives `segment` a new `length`, in place when the structure allows it
+    /// and at a new offset otherwise. `None` when there is no room, leaving
+    /// `segment` as it was.
+    fn resize(&mut self, segment: &mut Segment, length: usize) -> Option<()> {
+        let in_place = match self {
+            Partition::Segments(segments) => segments.resize(segment, length),
+            Partition::Buddy(buddy) => buddy.resize(segment, length),
+            Partition::Fixed(partitions) => partitions.resize(segment, length),
+            Partition::Frames(_) => return None,
+        };
+        if in_place {
+            segment.length = length;
+            return Some(());
+        }
+        let moved = match self {
+            // The blocks of the segment can be reused, so it is freed first
+            // and taken back if nothing fits.
+            Partition::Segments(segments) => {
+                segments.free(segment.clone());
+                let moved = segments.alloc(length);
+                if moved.is_none() {
+                    segments.alloc_segment(segment);
+                }
+                moved
+            }
+            Partition::Buddy(buddy) => {
+                let moved = buddy.alloc(length);
+                if moved.is_some() {
+                    buddy.free(segment.clone());
+                }
+                moved
+            }
+            Partition::Fixed(partitions) => {
+                let moved = partitions.alloc(length);
+                if moved.is_some() {
+                    partitions.free(segment.clone());
+                }
+                moved
+            }
+            Partition::Frames(_) => None,
+        }?;
+        *segment = moved;
+        Some(())
+    }
+
+    fn max_request(&self, config: &MemoryConfig) -> usize {
+        match self {
+            Partition::Segments(segments) => segments.free_space().capacity,
+            Partition::Buddy(buddy) => buddy.max_request(),
+            Partition::Fixed(partitions) => partitions.max_request(),
+            // Only the resident set has to fit, any number of pages does.
+            Partition::Frames(frame_pool) if frame_pool.frames() >= config.frames_per_process => {
+                usize::MAX
+            }
+            Partition::Frames(frame_pool) => frame_pool.frames() * config.page_size,
+        }
+    }
+
+    fn free_space(&self) -> FreeSpace {
+        match self {
+            Partition::Segments(segments) => segments.free_space(),
+            Partition::Buddy(buddy) => buddy.free_space(),
+            Partition::Frames(frame_pool) => frame_pool.free_space(),
+            Partition::Fixed(partitions) => partitions.free_space(),
+        }
+    }
+
+    fn check_invariants(&self) -> Result<(), String> {
+        match self {
+            Partition::Segments(segments) => segments.check_invariants(),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct MemoryManager {
+    real_time_partition: Partition,
+    user_partition: Partition,
+    config: MemoryConfig,
+    /// Check the free lists after every operation.
+    paranoid: bool,
+}
 
 #[derive(Debug, PartialEq)]
 pub enum AllocMemoryError {
@@ -15,18 +278,58 @@ pub enum AllocMemoryError {
 }
 
 impl MemoryManager {
-    pub fn new() -> Self {
-        let real_time_initial_segment = Segment {
-            offset: 0,
-            length: REAL_TIME_PARTITION_SIZE,
-        };
-        let user_initial_segment = Segment {
-            offset: REAL_TIME_PARTITION_SIZE,
-            length: USER_PARTITION_SIZE,
-        };
+    pub fn new(config: &MemoryConfig) -> Self {
         Self {
-            real_time_partition: SegmentList::new(vec![real_time_initial_segment]),
-            user_partition: SegmentList::new(vec![user_initial_segment]),
+            real_time_partition: MemoryManager::empty_partition(config, 0),
+            user_partition: MemoryManager::empty_partition(config, 1),
+            config: config.clone(),
+            paranoid: false,
+        }
+    }
+
+    /// Makes every operation check the free lists afterwards, panicking on
+    /// the first broken invariant.
+    pub fn set_paranoid(&mut self, paranoid: bool) {
+        self.paranoid = paranoid;
+    }
+
+    fn check_invariants(&self) {
+        if !self.paranoid {
+            return;
+        }
+        for (name, partition) in [
+            ("real-time", &self.real_time_partition),
+            ("user", &self.user_partition),
+        ] {
+            if let Err(error) = partition.check_invariants() {
+                panic!("{} memory partition: {}", name, error);
+            }
+        }
+    }
+
+    /// The partition processes of `priority` are placed in, with nothing allocated.
+    fn empty_partition(config: &MemoryConfig, priority: usize) -> Partition {
+        match priority {
+            0 => Partition::new(
+                config,
+                0,
+                config.real_time_partition_size,
+                &config.real_time_fixed_partitions,
+            ),
+            _ => Partition::new(
+                config,
+                config.real_time_partition_size,
+                config.user_partition_size,
+                &config.user_fixed_partitions,
+            ),
+        }
+    }
+
+    /// Largest number of blocks a process of `priority` could ever be given.
+    pub fn max_request(&self, priority: usize) -> usize {
+        match priority {
+            0 => self.real_time_partition.max_request(&self.config),
+            _ => self.user_partition.max_request(&self.config),
         }
     }
 
@@ -34,27 +337,239 @@ impl MemoryManager {
         &mut self,
         process_priority: usize,
         size: usize,
+    ) -> Result<AddressSpace, AllocMemoryError> {
+        let partition = match process_priority {
+            0 => &mut self.real_time_partition,
+            _ => &mut self.user_partition,
+        };
+        let allocated = match partition.alloc(size, &self.config) {
+            None if size > partition.max_request(&self.config) => {
+                Err(AllocMemoryError::Unsupported)
+            }
+            None => Err(AllocMemoryError::Unavailable),
+            Some(address_space) => Ok(address_space),
+        };
+        self.check_invariants();
+        allocated
+    }
+
+    /// Gives every non-empty segment of `sizes` its own place in the
+    /// partition of `priority`. With the `Paged` backend the segments are
+    /// paged as a whole.
+    pub fn alloc_segments(
+        &mut self,
+        priority: usize,
+        sizes: SegmentSizes,
+    ) -> Result<AddressSpace, AllocMemoryError> {
+        if self.config.backend == MemoryBackend::Paged {
+            return self.alloc(priority, sizes.total());
+        }
+        let kinds = SegmentKind::ALL
+            .into_iter()
+            .filter(|kind| sizes.size(*kind) > 0)
+            .collect::<Vec<_>>();
+        let partition = match priority {
+            0 => &mut self.real_time_partition,
+            _ => &mut self.user_partition,
+        };
+        let mut entries = Vec::new();
+        for kind in kinds.iter() {
+            match partition.alloc(sizes.size(*kind), &self.config) {
+                Some(AddressSpace::Contiguous(segment)) => entries.push((*kind, segment)),
+                _ => {
+                    for (_, segment) in entries {
+                        partition.free(AddressSpace::Contiguous(segment));
+                    }
+                    self.check_invariants();
+                    // Whether the segments would fit with nothing else allocated.
+                    let mut empty = MemoryManager::empty_partition(&self.config, priority);
+                    let fits_when_empty = kinds
+                        .iter()
+                        .all(|kind| empty.alloc(sizes.size(*kind), &self.config).is_some());
+                    return Err(match fits_when_empty {
+                        true => AllocMemoryError::Unavailable,
+                        false => AllocMemoryError::Unsupported,
+                    });
+                }
+            }
+        }
+        self.check_invariants();
+        Ok(AddressSpace::Segmented(SegmentTable::new(entries)))
+    }
+
+    pub fn alloc_request(
+        &mut self,
+        priority: usize,
+        request: &MemoryRequest,
+    ) -> Result<AddressSpace, AllocMemoryError> {
+        match request {
+            MemoryRequest::Blocks(size) => self.alloc(priority, *size),
+            MemoryRequest::Segments(sizes) => self.alloc_segments(priority, *sizes),
+        }
+    }
+
+    /// Sets the length of the heap of `address_space` (see
+    /// [`AddressSpace::heap`]) to `length`, in place when the blocks after
+    /// it allow and moving it otherwise. Returns the heap as it was.
+    pub fn resize(
+        &mut self,
+        priority: usize,
+        address_space: &mut AddressSpace,
+        length: usize,
     ) -> Result<Segment, AllocMemoryError> {
-        match process_priority {
-            0 => match self.real_time_partition.alloc(size) {
-                None if size > REAL_TIME_PARTITION_SIZE => Err(AllocMemoryError::Unsupported),
-                None => Err(AllocMemoryError::Unavailable),
-                Some(segment) => Ok(segment),
-            },
-            _ => match self.user_partition.alloc(size) {
-                None if size > USER_PARTITION_SIZE => Err(AllocMemoryError::Unsupported),
-                None => Err(AllocMemoryError::Unavailable),
-                Some(segment) => Ok(segment),
-            },
+        let max_request = self.max_request(priority);
+        let Some(heap) = address_space.heap_mut() else {
+            return Err(AllocMemoryError::Unsupported);
+        };
+        if length == 0 || length > max_request {
+            return Err(AllocMemoryError::Unsupported);
+        }
+        let partition = match priority {
+            0 => &mut self.real_time_partition,
+            _ => &mut self.user_partition,
+        };
+        let from = heap.clone();
+        let resized = partition.resize(heap, length);
+        self.check_invariants();
+        resized.ok_or(AllocMemoryError::Unavailable)?;
+        Ok(from)
+    }
+
+    /// Free blocks of the partition a process of `priority` is placed in.
+    pub fn free_blocks(&self, priority: usize) -> usize {
+        match priority {
+            0 => self.real_time_partition.free_space().free_blocks,
+            _ => self.user_partition.free_space().free_blocks,
+        }
+    }
+
+    /// What an address space was asked as, what it is given back after a swap.
+    pub fn request(&self, address_space: &AddressSpace) -> MemoryRequest {
+        match address_space {
+            AddressSpace::Contiguous(segment) => MemoryRequest::Blocks(segment.length),
+            AddressSpace::Segmented(segment_table) => {
+                MemoryRequest::Segments(segment_table.sizes())
+            }
+            AddressSpace::Paged(page_table) => {
+                MemoryRequest::Blocks(page_table.pages() * self.config.page_size)
+            }
+            AddressSpace::Swapped(request) => *request,
+        }
+    }
+
+    /// Blocks an address space holds in memory right now.
+    pub fn resident_blocks(&self, address_space: &AddressSpace) -> usize {
+        match address_space {
+            AddressSpace::Contiguous(segment) => segment.length,
+            AddressSpace::Segmented(segment_table) => segment_table.sizes().total(),
+            AddressSpace::Paged(page_table) => page_table.frames().len() * self.config.page_size,
+            AddressSpace::Swapped(_) => 0,
+        }
+    }
+
+    /// Blocks a request of `size` would hold once allocated.
+    pub fn required_blocks(&self, size: usize) -> usize {
+        match self.config.backend {
+            MemoryBackend::Paged => {
+                let pages = size.div_ceil(self.config.page_size);
+                pages.min(self.config.frames_per_process) * self.config.page_size
+            }
+            _ => size,
         }
     }
 
-    pub fn free(&mut self, segment: Segment) {
-        if segment.offset < REAL_TIME_PARTITION_SIZE {
-            self.real_time_partition.free(segment);
-        } else {
-            self.user_partition.free(segment);
+    /// The fixed partition queue a process of `priority` and `size` waits
+    /// in, `None` when waiting processes share a single queue.
+    pub fn queue_of(&self, priority: usize, size: usize) -> Option<usize> {
+        match (priority, &self.real_time_partition, &self.user_partition) {
+            (0, Partition::Fixed(partitions), _) | (1.., _, Partition::Fixed(partitions)) => {
+                partitions.queue(size)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether compacting the partition of `priority` would let a request of
+    /// `size` blocks, that fits in no hole, be served.
+    pub fn needs_compaction(&self, priority: usize, size: usize) -> bool {
+        let partition = match priority {
+            0 => &self.real_time_partition,
+            _ => &self.user_partition,
+        };
+        let space = partition.free_space();
+        self.config.compaction
+            && matches!(partition, Partition::Segments(_))
+            && space.largest_hole < size
+            && size <= space.free_blocks
+    }
+
+    /// Slides the segments of the partition of `priority` together, moving
+    /// the address spaces in `address_spaces` that belong to it. Every live
+    /// address space must be given; `None` when the partition was left as is
+    /// because one is missing or its backend cannot be compacted.
+    pub fn compact<'a>(
+        &mut self,
+        priority: usize,
+        address_spaces: impl IntoIterator<Item = &'a mut AddressSpace>,
+    ) -> Option<Compaction> {
+        let (partition, range) = match priority {
+            0 => (
+                &mut self.real_time_partition,
+                0..self.config.real_time_partition_size,
+            ),
+            _ => (
+                &mut self.user_partition,
+                self.config.real_time_partition_size
+                    ..self.config.real_time_partition_size + self.config.user_partition_size,
+            ),
+        };
+        let Partition::Segments(segments) = partition else {
+            return None;
+        };
+        let mut allocated = address_spaces
+            .into_iter()
+            .flat_map(|address_space| match address_space {
+                AddressSpace::Contiguous(segment) => vec![segment],
+                AddressSpace::Segmented(segment_table) => segment_table.segments_mut().collect(),
+                _ => vec![],
+            })
+            .filter(|segment| range.contains(&segment.offset))
+            .collect::<Vec<_>>();
+        let compaction = segments.compact(&mut allocated);
+        self.check_invariants();
+        compaction
+    }
+
+    /// Ticks a compaction moving `moved_blocks` takes.
+    pub fn compaction_cost(&self, moved_blocks: usize) -> usize {
+        moved_blocks.div_ceil(self.config.compaction_blocks_per_tick)
+    }
+
+    pub fn free_space(&self) -> [(StorageArea, FreeSpace); 2] {
+        [
+            (
+                StorageArea::RealTimeMemory,
+                self.real_time_partition.free_space(),
+            ),
+            (StorageArea::UserMemory, self.user_partition.free_space()),
+        ]
+    }
+
+    pub fn free(&mut self, address_space: AddressSpace) {
+        match address_space.first_offset() {
+            Some(offset) if offset < self.config.real_time_partition_size => {
+                self.real_time_partition.free(address_space)
+            }
+            Some(_) => self.user_partition.free(address_space),
+            None => {}
         }
+        self.check_invariants();
+    }
+}
+
+impl Default for MemoryManager {
+    fn default() -> Self {
+        Self::new(&MemoryConfig::default())
     }
 }
 
@@ -67,83 +582,345 @@ mod tests {
 
         #[test]
         fn real_time_process_allocs_from_first_partition() {
-            let mut memory_manager = MemoryManager::new();
+            let mut memory_manager = MemoryManager::default();
             let alloc_segment = memory_manager.alloc(0, 10);
             assert_eq!(
                 alloc_segment,
-                Ok(Segment {
+                Ok(AddressSpace::Contiguous(Segment {
                     offset: 0,
                     length: 10
-                })
+                }))
             );
         }
 
         #[test]
         fn user_process_allocs_from_second_partition() {
-            let mut memory_manager = MemoryManager::new();
+            let mut memory_manager = MemoryManager::default();
             let alloc_segment = memory_manager.alloc(1, 10);
             assert_eq!(
                 alloc_segment,
-                Ok(Segment {
+                Ok(AddressSpace::Contiguous(Segment {
                     offset: 64,
                     length: 10
-                })
+                }))
             );
         }
 
         #[test]
         fn real_time_process_dont_access_user_partition() {
-            let mut memory_manager = MemoryManager::new();
+            let mut memory_manager = MemoryManager::default();
             let alloc_segment = memory_manager.alloc(0, 65);
             assert_eq!(alloc_segment, Err(AllocMemoryError::Unsupported));
         }
     }
 
+    mod buddy {
+        use super::*;
+
+        fn create_buddy_memory_manager() -> MemoryManager {
+            MemoryManager::new(&MemoryConfig {
+                backend: MemoryBackend::Buddy,
+                ..MemoryConfig::default()
+            })
+        }
+
+        #[test]
+        fn rounds_blocks_up_to_a_power_of_two() {
+            let mut memory_manager = create_buddy_memory_manager();
+            let alloc_segment = memory_manager.alloc(1, 100);
+            assert_eq!(
+                alloc_segment,
+                Ok(AddressSpace::Contiguous(Segment {
+                    offset: 64 + 768,
+                    length: 100
+                }))
+            );
+            let [_, (_, user_space)] = memory_manager.free_space();
+            assert_eq!(user_space.internal_fragmentation, 28);
+            memory_manager.free(alloc_segment.unwrap());
+            let [_, (_, user_space)] = memory_manager.free_space();
+            assert_eq!(user_space.free_blocks, 960);
+            assert_eq!(user_space.internal_fragmentation, 0);
+        }
+
+        #[test]
+        fn requests_above_the_largest_block_are_unsupported() {
+            let mut memory_manager = create_buddy_memory_manager();
+            assert_eq!(memory_manager.max_request(1), 512);
+            assert_eq!(
+                memory_manager.alloc(1, 600),
+                Err(AllocMemoryError::Unsupported)
+            );
+        }
+    }
+
+    mod fixed {
+        use super::*;
+
+        #[test]
+        fn gives_the_smallest_fitting_partition() {
+            let mut memory_manager = MemoryManager::new(&MemoryConfig {
+                backend: MemoryBackend::Fixed,
+                ..MemoryConfig::default()
+            });
+            assert_eq!(memory_manager.max_request(0), 32);
+            assert_eq!(memory_manager.max_request(1), 512);
+            assert_eq!(
+                memory_manager.alloc(1, 100),
+                Ok(AddressSpace::Contiguous(Segment {
+                    offset: 64 + 64,
+                    length: 100
+                }))
+            );
+            let [_, (_, user_space)] = memory_manager.free_space();
+            assert_eq!(user_space.internal_fragmentation, 28);
+            assert_eq!(user_space.holes, 3);
+            assert_eq!(memory_manager.queue_of(1, 100), None);
+        }
+    }
+
+    mod alloc_segments {
+        use super::*;
+
+        const SIZES: SegmentSizes = SegmentSizes {
+            code: 16,
+            data: 0,
+            stack: 24,
+        };
+
+        #[test]
+        fn places_each_segment_on_its_own() {
+            let mut memory_manager = MemoryManager::new(&MemoryConfig {
+                user_partition_size: 100,
+                ..MemoryConfig::default()
+            });
+            let first = memory_manager.alloc(1, 20).unwrap();
+            memory_manager.alloc(1, 50).unwrap();
+            memory_manager.free(first);
+            let address_space = memory_manager.alloc_segments(1, SIZES).unwrap();
+            assert_eq!(
+                address_space.translate(SegmentKind::Code, 15),
+                Ok(Some(64 + 15))
+            );
+            assert_eq!(
+                address_space.translate(SegmentKind::Stack, 0),
+                Ok(Some(64 + 70))
+            );
+            assert_eq!(
+                memory_manager.request(&address_space),
+                MemoryRequest::Segments(SIZES)
+            );
+            memory_manager.free(address_space);
+            assert_eq!(memory_manager.free_blocks(1), 50);
+        }
+
+        #[test]
+        fn gives_back_placed_segments_when_one_does_not_fit() {
+            let mut memory_manager = MemoryManager::new(&MemoryConfig {
+                user_partition_size: 100,
+                ..MemoryConfig::default()
+            });
+            let first = memory_manager.alloc(1, 20).unwrap();
+            memory_manager.alloc(1, 60).unwrap();
+            memory_manager.free(first);
+            assert_eq!(
+                memory_manager.alloc_segments(1, SIZES),
+                Err(AllocMemoryError::Unavailable)
+            );
+            assert_eq!(memory_manager.free_blocks(1), 40);
+            let too_large = SegmentSizes { stack: 90, ..SIZES };
+            assert_eq!(
+                memory_manager.alloc_segments(1, too_large),
+                Err(AllocMemoryError::Unsupported)
+            );
+        }
+    }
+
+    mod resize {
+        use super::*;
+
+        fn create_memory_manager(backend: MemoryBackend) -> MemoryManager {
+            MemoryManager::new(&MemoryConfig {
+                backend,
+                user_partition_size: 64,
+                ..MemoryConfig::default()
+            })
+        }
+
+        #[test]
+        fn grows_in_place_then_moves_then_waits() {
+            let mut memory_manager = create_memory_manager(MemoryBackend::Segments);
+            let mut first = memory_manager.alloc(1, 10).unwrap();
+            let mut second = memory_manager.alloc(1, 10).unwrap();
+            assert_eq!(
+                memory_manager.resize(1, &mut second, 20),
+                Ok(Segment {
+                    offset: 74,
+                    length: 10
+                })
+            );
+            assert_eq!(second.heap().unwrap().offset, 74);
+            assert!(memory_manager.resize(1, &mut first, 30).is_ok());
+            assert_eq!(
+                first,
+                AddressSpace::Contiguous(Segment {
+                    offset: 94,
+                    length: 30
+                })
+            );
+            assert_eq!(
+                memory_manager.resize(1, &mut second, 40),
+                Err(AllocMemoryError::Unavailable)
+            );
+            assert_eq!(second.heap().unwrap().length, 20);
+            assert_eq!(
+                memory_manager.resize(1, &mut second, 65),
+                Err(AllocMemoryError::Unsupported)
+            );
+            assert!(memory_manager.resize(1, &mut first, 4).is_ok());
+            assert_eq!(memory_manager.free_blocks(1), 64 - 24);
+        }
+
+        #[test]
+        fn grows_within_the_buddy_block() {
+            let mut memory_manager = create_memory_manager(MemoryBackend::Buddy);
+            let mut address_space = memory_manager.alloc(1, 5).unwrap();
+            memory_manager.alloc(1, 8).unwrap();
+            assert!(memory_manager.resize(1, &mut address_space, 8).is_ok());
+            assert_eq!(address_space.heap().unwrap().offset, 64);
+            assert!(memory_manager.resize(1, &mut address_space, 9).is_ok());
+            assert_eq!(address_space.heap().unwrap().offset, 64 + 16);
+            let [_, (_, user_space)] = memory_manager.free_space();
+            assert_eq!(user_space.free_blocks, 64 - 8 - 16);
+        }
+
+        #[test]
+        fn resizes_the_data_segment() {
+            let mut memory_manager = create_memory_manager(MemoryBackend::Segments);
+            let sizes = SegmentSizes {
+                code: 4,
+                data: 2,
+                stack: 0,
+            };
+            let mut address_space = memory_manager.alloc_segments(1, sizes).unwrap();
+            assert!(memory_manager.resize(1, &mut address_space, 6).is_ok());
+            assert_eq!(
+                memory_manager.request(&address_space),
+                MemoryRequest::Segments(SegmentSizes { data: 6, ..sizes })
+            );
+            let mut swapped = AddressSpace::Swapped(MemoryRequest::Blocks(4));
+            assert_eq!(
+                memory_manager.resize(1, &mut swapped, 8),
+                Err(AllocMemoryError::Unsupported)
+            );
+        }
+    }
+
     mod free {
         use super::*;
 
         #[test]
         fn real_time_partition_segments_can_be_reused() {
-            let mut memory_manager = MemoryManager::new();
+            let mut memory_manager = MemoryManager::default();
             let alloc_segment = memory_manager.alloc(0, 10);
             assert_eq!(
                 alloc_segment,
-                Ok(Segment {
+                Ok(AddressSpace::Contiguous(Segment {
                     offset: 0,
                     length: 10
-                })
+                }))
             );
             memory_manager.free(alloc_segment.unwrap());
             let alloc_segment = memory_manager.alloc(0, 10);
             assert_eq!(
                 alloc_segment,
-                Ok(Segment {
+                Ok(AddressSpace::Contiguous(Segment {
                     offset: 0,
                     length: 10
-                })
+                }))
             );
         }
 
         #[test]
         fn user_partition_segments_can_be_reused() {
-            let mut memory_manager = MemoryManager::new();
+            let mut memory_manager = MemoryManager::default();
             let alloc_segment = memory_manager.alloc(1, 10);
             assert_eq!(
                 alloc_segment,
-                Ok(Segment {
+                Ok(AddressSpace::Contiguous(Segment {
                     offset: 64,
                     length: 10
-                })
+                }))
             );
             memory_manager.free(alloc_segment.unwrap());
             let alloc_segment = memory_manager.alloc(1, 10);
             assert_eq!(
                 alloc_segment,
-                Ok(Segment {
+                Ok(AddressSpace::Contiguous(Segment {
                     offset: 64,
                     length: 10
+                }))
+            );
+        }
+
+        #[test]
+        #[should_panic(
+            expected = "user memory partition: blocks 64..74 were freed while partly free"
+        )]
+        fn paranoid_mode_panics_on_double_frees() {
+            let mut memory_manager = MemoryManager::default();
+            memory_manager.set_paranoid(true);
+            let address_space = memory_manager.alloc(1, 10).unwrap();
+            memory_manager.free(address_space.clone());
+            memory_manager.free(address_space);
+        }
+    }
+
+    mod compact {
+        use super::*;
+
+        #[test]
+        fn moves_user_segments_and_keeps_real_time_ones() {
+            let mut memory_manager = MemoryManager::new(&MemoryConfig {
+                user_partition_size: 30,
+                compaction: true,
+                ..MemoryConfig::default()
+            });
+            let mut real_time = memory_manager.alloc(0, 10).unwrap();
+            let first = memory_manager.alloc(1, 10).unwrap();
+            let mut second = memory_manager.alloc(1, 10).unwrap();
+            memory_manager.free(first);
+            assert!(memory_manager.needs_compaction(1, 15));
+            assert!(!memory_manager.needs_compaction(1, 25));
+            let compaction = memory_manager.compact(1, [&mut real_time, &mut second]);
+            assert_eq!(
+                compaction,
+                Some(Compaction {
+                    moved_blocks: 10,
+                    relocated: 1
                 })
             );
+            assert_eq!(
+                second,
+                AddressSpace::Contiguous(Segment {
+                    offset: 64,
+                    length: 10
+                })
+            );
+            assert_eq!(memory_manager.alloc(1, 20).map(|_| ()), Ok(()));
+            assert_eq!(memory_manager.compaction_cost(10), 1);
+        }
+
+        #[test]
+        fn buddy_partitions_are_left_as_is() {
+            let mut memory_manager = MemoryManager::new(&MemoryConfig {
+                backend: MemoryBackend::Buddy,
+                compaction: true,
+                ..MemoryConfig::default()
+            });
+            let mut address_space = memory_manager.alloc(1, 10).unwrap();
+            assert!(!memory_manager.needs_compaction(1, 600));
+            assert_eq!(memory_manager.compact(1, [&mut address_space]), None);
         }
     }
 }
@@ -0,0 +1,228 @@
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+/// Which resident page a `PageTable` evicts when a fault finds no free frame.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum ReplacementPolicy {
+    /// The page loaded the longest time ago.
+    #[default]
+    Fifo,
+    /// The page used the longest time ago.
+    Lru,
+    /// FIFO giving a second chance to pages referenced since the hand last passed.
+    Clock,
+    /// The page whose next use is farthest in the future.
+    Optimal,
+}
+
+impl FromStr for ReplacementPolicy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "fifo" => Ok(ReplacementPolicy::Fifo),
+            "lru" => Ok(ReplacementPolicy::Lru),
+            "clock" => Ok(ReplacementPolicy::Clock),
+            "optimal" => Ok(ReplacementPolicy::Optimal),
+            _ => Err(format!(
+                "unknown replacement policy `{}`, expected one of fifo, lru, clock, optimal",
+                name
+            )),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PageAccess {
+    Hit,
+    /// The page was loaded in the frame at `frame`, replacing `evicted`.
+    Fault {
+        frame: usize,
+        evicted: Option<usize>,
+    },
+    /// The page is outside the address space.
+    Unmapped,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct Frame {
+    offset: usize,
+    page: Option<usize>,
+    loaded_at: usize,
+    last_used: usize,
+    referenced: bool,
+}
+
+/// Page table of a process with a fixed resident set of frames, replacing
+/// pages locally when a fault finds every frame in use.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PageTable {
+    pages: usize,
+    policy: ReplacementPolicy,
+    frames: Vec<Frame>,
+    /// Next frame inspected by the clock policy.
+    hand: usize,
+    /// Accesses so far, used to order loads and uses.
+    time: usize,
+}
+
+impl PageTable {
+    /// `frames` holds the offsets of the frames given to the process.
+    pub fn new(pages: usize, frames: Vec<usize>, policy: ReplacementPolicy) -> Self {
+        PageTable {
+            pages,
+            policy,
+            frames: frames
+                .into_iter()
+                .map(|offset| Frame {
+                    offset,
+                    page: None,
+                    loaded_at: 0,
+                    last_used: 0,
+                    referenced: false,
+                })
+                .collect(),
+            hand: 0,
+            time: 0,
+        }
+    }
+
+    pub fn pages(&self) -> usize {
+        self.pages
+    }
+
+    /// Offsets of the frames of the resident set.
+    pub fn frames(&self) -> Vec<usize> {
+        self.frames.iter().map(|frame| frame.offset).collect()
+    }
+
+    /// Pages currently in a frame, in frame order.
+    pub fn resident_pages(&self) -> Vec<usize> {
+        self.frames.iter().filter_map(|frame| frame.page).collect()
+    }
+
+    /// Touches `page`; `future` holds the pages the process will touch next,
+    /// in order, and is only looked at by the optimal policy.
+    pub fn access(&mut self, page: usize, future: &[usize]) -> PageAccess {
+        if page >= self.pages || self.frames.is_empty() {
+            return PageAccess::Unmapped;
+        }
+        self.time += 1;
+        if let Some(frame) = self
+            .frames
+            .iter_mut()
+            .find(|frame| frame.page == Some(page))
+        {
+            frame.last_used = self.time;
+            frame.referenced = true;
+            return PageAccess::Hit;
+        }
+        let index = match self.frames.iter().position(|frame| frame.page.is_none()) {
+            Some(index) => index,
+            None => self.victim(future),
+        };
+        let time = self.time;
+        let frame = &mut self.frames[index];
+        let evicted = frame.page.replace(page);
+        frame.loaded_at = time;
+        frame.last_used = time;
+        frame.referenced = true;
+        PageAccess::Fault {
+            frame: frame.offset,
+            evicted,
+        }
+    }
+
+    fn victim(&mut self, future: &[usize]) -> usize {
+        let indexed = self.frames.iter().enumerate();
+        match self.policy {
+            ReplacementPolicy::Fifo => indexed.min_by_key(|(_, frame)| frame.loaded_at).unwrap().0,
+            ReplacementPolicy::Lru => indexed.min_by_key(|(_, frame)| frame.last_used).unwrap().0,
+            ReplacementPolicy::Optimal => {
+                indexed
+                    .max_by_key(|(index, frame)| {
+                        let next_use = future
+                            .iter()
+                            .position(|page| Some(*page) == frame.page)
+                            .unwrap_or(usize::MAX);
+                        (next_use, Reverse(*index))
+                    })
+                    .unwrap()
+                    .0
+            }
+            ReplacementPolicy::Clock => loop {
+                let index = self.hand;
+                self.hand = (self.hand + 1) % self.frames.len();
+                if !self.frames[index].referenced {
+                    break index;
+                }
+                self.frames[index].referenced = false;
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `references` through a table of `frames` frames and counts the faults.
+    fn count_faults(policy: ReplacementPolicy, frames: usize, references: &[usize]) -> usize {
+        let mut page_table = PageTable::new(8, (0..frames).collect(), policy);
+        (0..references.len())
+            .filter(|index| {
+                let access = page_table.access(references[*index], &references[index + 1..]);
+                matches!(access, PageAccess::Fault { .. })
+            })
+            .count()
+    }
+
+    const REFERENCES: [usize; 20] = [7, 0, 1, 2, 0, 3, 0, 4, 2, 3, 0, 3, 2, 1, 2, 0, 1, 7, 0, 1];
+
+    mod access {
+        use super::*;
+
+        #[test]
+        fn textbook_fault_counts() {
+            assert_eq!(count_faults(ReplacementPolicy::Fifo, 3, &REFERENCES), 15);
+            assert_eq!(count_faults(ReplacementPolicy::Lru, 3, &REFERENCES), 12);
+            assert_eq!(count_faults(ReplacementPolicy::Optimal, 3, &REFERENCES), 9);
+            assert_eq!(count_faults(ReplacementPolicy::Clock, 3, &REFERENCES), 14);
+        }
+
+        #[test]
+        fn fifo_shows_beladys_anomaly() {
+            let references = [1, 2, 3, 4, 1, 2, 5, 1, 2, 3, 4, 5];
+            assert_eq!(count_faults(ReplacementPolicy::Fifo, 3, &references), 9);
+            assert_eq!(count_faults(ReplacementPolicy::Fifo, 4, &references), 10);
+        }
+
+        #[test]
+        fn reports_frame_and_evicted_page() {
+            let mut page_table = PageTable::new(4, vec![32, 48], ReplacementPolicy::Fifo);
+            assert_eq!(
+                page_table.access(1, &[]),
+                PageAccess::Fault {
+                    frame: 32,
+                    evicted: None
+                }
+            );
+            assert_eq!(page_table.access(1, &[]), PageAccess::Hit);
+            page_table.access(2, &[]);
+            assert_eq!(
+                page_table.access(3, &[]),
+                PageAccess::Fault {
+                    frame: 32,
+                    evicted: Some(1)
+                }
+            );
+            assert_eq!(page_table.resident_pages(), vec![3, 2]);
+        }
+
+        #[test]
+        fn pages_outside_the_table_are_unmapped() {
+            let mut page_table = PageTable::new(2, vec![0], ReplacementPolicy::Lru);
+            assert_eq!(page_table.access(2, &[]), PageAccess::Unmapped);
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::structures::segment_list::Segment;
 
+use super::parse_error::{read_file, ParseError, SourceLine};
+
 #[derive(Debug)]
 pub enum DiskOperationDefinition {
     Create {
@@ -13,42 +15,159 @@ pub enum DiskOperationDefinition {
     },
 }
 
-pub fn parse(files_path: &str) -> (usize, Vec<(char, Segment)>, Vec<DiskOperationDefinition>) {
-    let file_string = std::fs::read_to_string(files_path).unwrap();
-    let mut lines = file_string.lines();
+pub type FilesDefinition = (usize, Vec<(char, Segment)>, Vec<DiskOperationDefinition>);
+
+pub fn parse(files_path: &str) -> Result<FilesDefinition, Vec<ParseError>> {
+    parse_str(files_path, &read_file(files_path)?)
+}
 
-    let num_blocks = lines.next().unwrap().parse::<usize>().unwrap();
-    let num_disk_segments = lines.next().unwrap().parse::<usize>().unwrap();
+/// Parses the contents of a files file; `file` is only used in errors.
+pub fn parse_str(file: &str, contents: &str) -> Result<FilesDefinition, Vec<ParseError>> {
+    let mut lines = SourceLine::lines(file, contents);
+    let mut header = |name: &str| match lines.next() {
+        Some(line) => {
+            let fields = line.expect_fields(&[name])?;
+            line.number(&fields[0], name)
+        }
+        None => Err(ParseError::whole_file(
+            file,
+            format!("expected `{}`, found end of file", name),
+        )),
+    };
+    let num_blocks = header("num_blocks").map_err(|error| vec![error])?;
+    let num_disk_segments = header("num_files").map_err(|error| vec![error])?;
 
+    let mut errors = Vec::new();
     let mut alloc_disk_blocks: Vec<(char, Segment)> = Vec::new();
-    for _ in 0..num_disk_segments {
-        let params: Vec<&str> = lines.next().unwrap().split(", ").collect();
-        let file_name = params[0].chars().next().unwrap();
-        let offset = params[1].parse::<usize>().unwrap();
-        let length = params[2].parse::<usize>().unwrap();
-        alloc_disk_blocks.push((file_name, Segment { offset, length }));
+    for index in 0..num_disk_segments {
+        let Some(line) = lines.next() else {
+            errors.push(ParseError::whole_file(
+                file,
+                format!(
+                    "expected {} pre-allocated files, found {}",
+                    num_disk_segments, index
+                ),
+            ));
+            break;
+        };
+        match parse_segment(&line) {
+            Ok(segment) => alloc_disk_blocks.push(segment),
+            Err(error) => errors.push(error),
+        }
     }
 
     let mut sysfile_operations: Vec<DiskOperationDefinition> = Vec::new();
-    while let Some(line) = lines.next() {
-        let params: Vec<&str> = line.split(", ").collect();
-        let process_id = params[0].parse::<usize>().unwrap();
-        let operation_code = params[1].parse::<usize>().unwrap();
-        let file_name = params[2].chars().next().unwrap();
-        if operation_code == 0 {
-            let file_size = params[3].parse::<usize>().unwrap();
-            sysfile_operations.push(DiskOperationDefinition::Create {
-                process_id,
-                file_name,
-                file_size,
-            });
-        } else {
-            sysfile_operations.push(DiskOperationDefinition::Erase {
-                process_id,
-                file_name,
-            });
+    for line in lines {
+        match parse_operation(&line) {
+            Ok(operation) => sysfile_operations.push(operation),
+            Err(error) => errors.push(error),
         }
     }
 
-    (num_blocks, alloc_disk_blocks, sysfile_operations)
+    if errors.is_empty() {
+        Ok((num_blocks, alloc_disk_blocks, sysfile_operations))
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_segment(line: &SourceLine) -> Result<(char, Segment), ParseError> {
+    let fields = line.expect_fields(&["file_name", "offset", "blocks"])?;
+    let file_name = line.file_name(&fields[0])?;
+    let offset = line.number(&fields[1], "offset")?;
+    let length = line.number(&fields[2], "blocks")?;
+    Ok((file_name, Segment { offset, length }))
+}
+
+fn parse_operation(line: &SourceLine) -> Result<DiskOperationDefinition, ParseError> {
+    let fields = line.fields();
+    let Some(operation) = fields.get(1) else {
+        return Err(line.error(
+            line.end_column(),
+            String::from("expected `process_id, operation, file_name[, blocks]`"),
+        ));
+    };
+    match line.number(operation, "operation")? {
+        0 => {
+            let fields = line.expect_fields(&["process_id", "operation", "file_name", "blocks"])?;
+            Ok(DiskOperationDefinition::Create {
+                process_id: line.number(&fields[0], "process_id")?,
+                file_name: line.file_name(&fields[2])?,
+                file_size: line.number(&fields[3], "blocks")?,
+            })
+        }
+        1 => {
+            let fields = line.expect_fields(&["process_id", "operation", "file_name"])?;
+            Ok(DiskOperationDefinition::Erase {
+                process_id: line.number(&fields[0], "process_id")?,
+                file_name: line.file_name(&fields[2])?,
+            })
+        }
+        other => Err(line.error(
+            operation.column,
+            format!(
+                "expected operation `0` (create) or `1` (delete), found `{}`",
+                other
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_str {
+        use super::*;
+
+        #[test]
+        fn parses_headers_files_and_operations() {
+            let (num_blocks, alloc_disk_blocks, operations) =
+                parse_str("f", "10\n1\nX, 0, 2\n0, 0, A, 5\n1, 1, X\n\n").unwrap();
+            assert_eq!(num_blocks, 10);
+            assert_eq!(
+                alloc_disk_blocks,
+                vec![(
+                    'X',
+                    Segment {
+                        offset: 0,
+                        length: 2
+                    }
+                )]
+            );
+            assert_eq!(operations.len(), 2);
+            assert!(matches!(
+                operations[1],
+                DiskOperationDefinition::Erase {
+                    process_id: 1,
+                    file_name: 'X'
+                }
+            ));
+        }
+
+        #[test]
+        fn reports_missing_pre_allocated_files() {
+            let errors = parse_str("f", "10\n2\nX, 0, 2\n").err().unwrap();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].message, "expected 2 pre-allocated files, found 1");
+        }
+
+        #[test]
+        fn collects_errors_of_every_operation() {
+            let errors = parse_str("f", "10\n0\n0, 0, A\n0, 2, B\n0, 1, CD\n")
+                .err()
+                .unwrap();
+            let positions = errors
+                .iter()
+                .map(|error| (error.line, error.column))
+                .collect::<Vec<_>>();
+            assert_eq!(positions, vec![(3, 8), (4, 4), (5, 7)]);
+        }
+
+        #[test]
+        fn rejects_missing_header() {
+            let errors = parse_str("f", "\n").err().unwrap();
+            assert_eq!(errors[0].line, 0);
+        }
+    }
 }
@@ -0,0 +1,259 @@
+use std::fmt;
+
+/// A problem found while reading an input file, pointing at the offending text.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub file: String,
+    /// 1-based line, `0` when the error concerns the whole file.
+    pub line: usize,
+    /// 1-based column, in characters.
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn whole_file(file: &str, message: String) -> Self {
+        ParseError {
+            file: file.to_string(),
+            line: 0,
+            column: 0,
+            text: String::new(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "error: {}: {}", self.file, self.message);
+        }
+        let gutter = " ".repeat(self.line.to_string().len());
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(
+            f,
+            "{}--> {}:{}:{}",
+            gutter, self.file, self.line, self.column
+        )?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", self.line, self.text)?;
+        write!(
+            f,
+            "{} | {}^",
+            gutter,
+            " ".repeat(self.column.saturating_sub(1))
+        )
+    }
+}
+
+/// Reads a whole input file, reporting a failure as a [`ParseError`].
+pub(crate) fn read_file(path: &str) -> Result<String, Vec<ParseError>> {
+    std::fs::read_to_string(path).map_err(|error| {
+        vec![ParseError::whole_file(
+            path,
+            format!("could not read file: {}", error),
+        )]
+    })
+}
+
+/// A non-blank line of an input file, or a part of one.
+pub(crate) struct SourceLine<'a> {
+    file: &'a str,
+    number: usize,
+    line: &'a str,
+    text: &'a str,
+    /// Column of the first character of `text` in `line`.
+    first_column: usize,
+}
+
+/// A comma separated field of a [`SourceLine`], trimmed.
+pub(crate) struct Field<'a> {
+    pub text: &'a str,
+    pub column: usize,
+}
+
+impl<'a> SourceLine<'a> {
+    /// The non-blank lines of `contents`, keeping their line numbers.
+    pub fn lines(file: &'a str, contents: &'a str) -> impl Iterator<Item = SourceLine<'a>> {
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, text)| !text.trim().is_empty())
+            .map(move |(index, text)| SourceLine {
+                file,
+                number: index + 1,
+                line: text,
+                text,
+                first_column: 1,
+            })
+    }
+
+    /// Splits the line at the first `separator`, which belongs to neither part.
+    pub fn split_once(&self, separator: char) -> (SourceLine<'a>, Option<SourceLine<'a>>) {
+        let part = |text: &'a str, first_column: usize| SourceLine {
+            file: self.file,
+            number: self.number,
+            line: self.line,
+            text,
+            first_column,
+        };
+        match self.text.split_once(separator) {
+            Some((head, tail)) => {
+                let tail_column = self.first_column + head.chars().count() + 1;
+                (part(head, self.first_column), Some(part(tail, tail_column)))
+            }
+            None => (part(self.text, self.first_column), None),
+        }
+    }
+
+    pub fn error(&self, column: usize, message: String) -> ParseError {
+        ParseError {
+            file: self.file.to_string(),
+            line: self.number,
+            column,
+            text: self.line.to_string(),
+            message,
+        }
+    }
+
+    /// Column right after the last character of the line.
+    pub fn end_column(&self) -> usize {
+        self.first_column + self.text.trim_end().chars().count()
+    }
+
+    pub fn fields(&self) -> Vec<Field<'a>> {
+        let mut fields = Vec::new();
+        let mut start = 0;
+        for raw in self.text.split(',') {
+            let leading = raw.len() - raw.trim_start().len();
+            fields.push(self.field(raw.trim(), start + leading));
+            start += raw.len() + 1;
+        }
+        fields
+    }
+
+    /// Whitespace separated words.
+    pub fn words(&self) -> Vec<Field<'a>> {
+        self.text
+            .split_whitespace()
+            .map(|word| {
+                let start = word.as_ptr() as usize - self.text.as_ptr() as usize;
+                self.field(word, start)
+            })
+            .collect()
+    }
+
+    /// A field made of `text`, found `start` bytes into the part.
+    fn field(&self, text: &'a str, start: usize) -> Field<'a> {
+        Field {
+            text,
+            column: self.first_column + self.text[..start].chars().count(),
+        }
+    }
+
+    /// Splits the line into exactly `names.len()` fields.
+    pub fn expect_fields(&self, names: &[&str]) -> Result<Vec<Field<'a>>, ParseError> {
+        let fields = self.fields();
+        if fields.len() == names.len() {
+            return Ok(fields);
+        }
+        let column = match fields.get(names.len()) {
+            Some(extra) => extra.column,
+            None => self.end_column(),
+        };
+        Err(self.error(
+            column,
+            format!(
+                "expected {} comma separated fields ({}), found {}",
+                names.len(),
+                names.join(", "),
+                fields.len()
+            ),
+        ))
+    }
+
+    pub fn number(&self, field: &Field, name: &str) -> Result<usize, ParseError> {
+        field.text.parse::<usize>().map_err(|_| {
+            self.error(
+                field.column,
+                format!(
+                    "expected a non-negative integer for `{}`, found `{}`",
+                    name, field.text
+                ),
+            )
+        })
+    }
+
+    pub fn file_name(&self, field: &Field) -> Result<char, ParseError> {
+        let mut characters = field.text.chars();
+        match (characters.next(), characters.next()) {
+            (Some(file_name), None) => Ok(file_name),
+            _ => Err(self.error(
+                field.column,
+                format!(
+                    "expected a single character file name, found `{}`",
+                    field.text
+                ),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod fields {
+        use super::*;
+
+        #[test]
+        fn columns_point_at_trimmed_text() {
+            let line = SourceLine::lines("f", "1,  22 ,x").next().unwrap();
+            let fields = line
+                .fields()
+                .iter()
+                .map(|field| (field.text, field.column))
+                .collect::<Vec<_>>();
+            assert_eq!(fields, vec![("1", 1), ("22", 5), ("x", 9)]);
+        }
+    }
+
+    mod split_once {
+        use super::*;
+
+        #[test]
+        fn tail_columns_are_relative_to_the_line() {
+            let line = SourceLine::lines("f", "1, 2; 3  45").next().unwrap();
+            let (head, tail) = line.split_once(';');
+            assert_eq!(head.fields().len(), 2);
+            let words = tail
+                .unwrap()
+                .words()
+                .iter()
+                .map(|word| (word.text, word.column))
+                .collect::<Vec<_>>();
+            assert_eq!(words, vec![("3", 7), ("45", 10)]);
+        }
+    }
+
+    mod fmt {
+        use super::*;
+
+        #[test]
+        fn renders_compiler_style() {
+            let line = SourceLine::lines("input/processes.txt", "\n\n2, x, 3")
+                .next()
+                .unwrap();
+            let error = line.number(&line.fields()[1], "priority").unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                "error: expected a non-negative integer for `priority`, found `x`\n \
+                 --> input/processes.txt:3:4\n  \
+                 |\n\
+                 3 | 2, x, 3\n  \
+                 |    ^"
+            );
+        }
+    }
+}
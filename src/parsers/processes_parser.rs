@@ -1,37 +1,367 @@
+use std::str::FromStr;
+
+use super::parse_error::{read_file, Field, ParseError, SourceLine};
+use crate::resources::Resource;
+use crate::segmentation::{SegmentKind, SegmentSizes};
+
 pub struct ProcessDefinition {
     pub id: usize,
     pub init_time: usize,
     pub priority: usize,
     pub cpu_time: usize,
     pub num_memory_blocks: usize,
+    /// Sizes of the code, data and stack segments, when declared as
+    /// `code+data+stack`; `num_memory_blocks` is then their total.
+    pub segments: Option<SegmentSizes>,
     pub use_printer: bool,
     pub use_scanner: bool,
     pub use_modem: bool,
     pub use_sata: bool,
+    /// Memory operations listed after a `;`, run after every other instruction.
+    pub memory_operations: Vec<MemoryOperationDefinition>,
+    /// Resource requests listed among the memory operations.
+    pub resource_requests: Vec<ResourceRequestDefinition>,
+    /// Most units of each resource the process may hold at once, declared
+    /// after the `;` as `max:printer=2`.
+    pub max_claims: Vec<(Resource, usize)>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MemoryOperationDefinition {
+    /// Touches a page of a paged address space.
+    Access { page: usize },
+    /// Reads `offset` of a segment, written `code:N`, `data:N` or `stack:N`.
+    SegmentAccess { segment: SegmentKind, offset: usize },
+    /// Extends the memory of the process by `blocks`, written `grow:N`.
+    Grow { blocks: usize },
+    /// Gives back the last `blocks` of its memory, written `shrink:N`.
+    Shrink { blocks: usize },
+}
+
+/// Requests a unit of `resource`, held until the process terminates,
+/// written `request:scanner`, `request:printer`, ...
+#[derive(Debug, PartialEq)]
+pub struct ResourceRequestDefinition {
+    pub resource: Resource,
+    /// Memory operations listed before the request, run before it.
+    pub after: usize,
+}
+
+const FIELDS: [&str; 8] = [
+    "init_time",
+    "priority",
+    "cpu_time",
+    "memory_blocks",
+    "printer",
+    "scanner",
+    "modem",
+    "sata",
+];
+
+pub fn parse(processes_path: &str) -> Result<Vec<ProcessDefinition>, Vec<ParseError>> {
+    parse_str(processes_path, &read_file(processes_path)?)
 }
 
-pub fn parse(processes_path: &str) -> Vec<ProcessDefinition> {
+/// Parses the contents of a processes file; `file` is only used in errors.
+pub fn parse_str(file: &str, contents: &str) -> Result<Vec<ProcessDefinition>, Vec<ParseError>> {
     let mut process_definitions = Vec::new();
-    for (id, line) in std::fs::read_to_string(processes_path)
-        .unwrap()
-        .lines()
-        .enumerate()
-    {
-        let params: Vec<usize> = line
-            .split(", ")
-            .map(|x| x.parse::<usize>().unwrap())
-            .collect();
-        process_definitions.push(ProcessDefinition {
-            id,
-            init_time: params[0],
-            priority: params[1],
-            cpu_time: params[2],
-            num_memory_blocks: params[3],
-            use_printer: params[4] != 0,
-            use_scanner: params[5] != 0,
-            use_modem: params[6] != 0,
-            use_sata: params[7] != 0,
-        });
+    let mut errors = Vec::new();
+    for (id, line) in SourceLine::lines(file, contents).enumerate() {
+        match parse_line(id, &line) {
+            Ok(process_definition) => process_definitions.push(process_definition),
+            Err(error) => errors.push(error),
+        }
+    }
+    if errors.is_empty() {
+        Ok(process_definitions)
+    } else {
+        Err(errors)
+    }
+}
+
+fn parse_line(id: usize, line: &SourceLine) -> Result<ProcessDefinition, ParseError> {
+    let (definition, operations) = line.split_once(';');
+    let fields = definition.expect_fields(&FIELDS)?;
+    let (num_memory_blocks, segments) = parse_memory(line, &fields[3])?;
+    let params = fields
+        .iter()
+        .zip(FIELDS)
+        .map(|(field, name)| match name {
+            "memory_blocks" => Ok(num_memory_blocks),
+            _ => line.number(field, name),
+        })
+        .collect::<Result<Vec<usize>, ParseError>>()?;
+    let mut memory_operations = Vec::new();
+    let mut resource_requests = Vec::new();
+    let mut max_claims = Vec::new();
+    for word in operations.map_or_else(Vec::new, |operations| operations.words()) {
+        if let Some(claim) = word.text.strip_prefix("max:") {
+            max_claims.push(parse_max_claim(
+                line,
+                &Field {
+                    text: claim,
+                    column: word.column + 4,
+                },
+            )?);
+        } else if let Some(resource) = word.text.strip_prefix("request:") {
+            resource_requests.push(ResourceRequestDefinition {
+                resource: Resource::from_str(resource)
+                    .map_err(|message| line.error(word.column + 8, message))?,
+                after: memory_operations.len(),
+            });
+        } else {
+            memory_operations.push(parse_memory_operation(line, &word)?);
+        }
+    }
+    Ok(ProcessDefinition {
+        id,
+        init_time: params[0],
+        priority: params[1],
+        cpu_time: params[2],
+        num_memory_blocks: params[3],
+        segments,
+        use_printer: params[4] != 0,
+        use_scanner: params[5] != 0,
+        use_modem: params[6] != 0,
+        use_sata: params[7] != 0,
+        memory_operations,
+        resource_requests,
+        max_claims,
+    })
+}
+
+fn parse_memory_operation(
+    line: &SourceLine,
+    word: &Field,
+) -> Result<MemoryOperationDefinition, ParseError> {
+    let Some((name, argument)) = word.text.split_once(':') else {
+        let page = line.number(word, "page")?;
+        return Ok(MemoryOperationDefinition::Access { page });
+    };
+    let argument = Field {
+        text: argument,
+        column: word.column + name.chars().count() + 1,
+    };
+    match name {
+        "grow" => Ok(MemoryOperationDefinition::Grow {
+            blocks: line.number(&argument, "blocks")?,
+        }),
+        "shrink" => Ok(MemoryOperationDefinition::Shrink {
+            blocks: line.number(&argument, "blocks")?,
+        }),
+        _ => {
+            let segment = SegmentKind::from_str(name).map_err(|_| {
+                line.error(
+                    word.column,
+                    format!(
+                        "unknown memory operation `{}`, expected one of code, data, stack, grow, shrink",
+                        name
+                    ),
+                )
+            })?;
+            Ok(MemoryOperationDefinition::SegmentAccess {
+                segment,
+                offset: line.number(&argument, "offset")?,
+            })
+        }
+    }
+}
+
+/// Parses the `resource=units` of a `max:` claim.
+fn parse_max_claim(line: &SourceLine, claim: &Field) -> Result<(Resource, usize), ParseError> {
+    let Some((name, units)) = claim.text.split_once('=') else {
+        return Err(line.error(
+            claim.column,
+            format!("expected `resource=units`, found `{}`", claim.text),
+        ));
+    };
+    let resource = Resource::from_str(name).map_err(|message| line.error(claim.column, message))?;
+    let units = Field {
+        text: units,
+        column: claim.column + name.chars().count() + 1,
+    };
+    Ok((resource, line.number(&units, "units")?))
+}
+
+/// Parses the `memory_blocks` field, a number of blocks or the sizes of
+/// the segments as `code+data+stack`.
+fn parse_memory(
+    line: &SourceLine,
+    field: &Field,
+) -> Result<(usize, Option<SegmentSizes>), ParseError> {
+    if !field.text.contains('+') {
+        return Ok((line.number(field, "memory_blocks")?, None));
+    }
+    let mut column = field.column;
+    let parts = field
+        .text
+        .split('+')
+        .map(|text| {
+            let part = Field {
+                text: text.trim(),
+                column: column + text.chars().count() - text.trim_start().chars().count(),
+            };
+            column += text.chars().count() + 1;
+            part
+        })
+        .collect::<Vec<_>>();
+    let [code, data, stack] = parts.as_slice() else {
+        return Err(line.error(
+            field.column,
+            format!(
+                "expected `code+data+stack` for `memory_blocks`, found `{}`",
+                field.text
+            ),
+        ));
+    };
+    let segments = SegmentSizes {
+        code: line.number(code, "code")?,
+        data: line.number(data, "data")?,
+        stack: line.number(stack, "stack")?,
+    };
+    Ok((segments.total(), Some(segments)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod parse_str {
+        use super::*;
+
+        #[test]
+        fn skips_blank_lines() {
+            let definitions =
+                parse_str("p", "2, 0, 3, 64, 0, 0, 0, 0\n\n8,1,2,64,1,0,0,1\n\n").unwrap();
+            assert_eq!(definitions.len(), 2);
+            assert_eq!(definitions[1].id, 1);
+            assert_eq!(definitions[1].priority, 1);
+            assert!(definitions[1].use_printer);
+            assert!(definitions[1].use_sata);
+        }
+
+        #[test]
+        fn parses_memory_references() {
+            let definitions = parse_str("p", "2, 1, 3, 64, 0, 0, 0, 0; 0 1  0\n").unwrap();
+            assert_eq!(
+                definitions[0].memory_operations,
+                vec![
+                    MemoryOperationDefinition::Access { page: 0 },
+                    MemoryOperationDefinition::Access { page: 1 },
+                    MemoryOperationDefinition::Access { page: 0 },
+                ]
+            );
+            let errors = parse_str("p", "2, 1, 3, 64, 0, 0, 0, 0; 0 x\n")
+                .err()
+                .unwrap();
+            assert_eq!((errors[0].line, errors[0].column), (1, 28));
+        }
+
+        #[test]
+        fn parses_segments_and_segment_accesses() {
+            let definitions = parse_str(
+                "p",
+                "2, 1, 3, 8+4+0, 0, 0, 0, 0; code:7 data:0 3 grow:4 shrink:2\n",
+            )
+            .unwrap();
+            assert_eq!(definitions[0].num_memory_blocks, 12);
+            assert_eq!(
+                definitions[0].segments,
+                Some(SegmentSizes {
+                    code: 8,
+                    data: 4,
+                    stack: 0
+                })
+            );
+            assert_eq!(
+                definitions[0].memory_operations,
+                vec![
+                    MemoryOperationDefinition::SegmentAccess {
+                        segment: SegmentKind::Code,
+                        offset: 7
+                    },
+                    MemoryOperationDefinition::SegmentAccess {
+                        segment: SegmentKind::Data,
+                        offset: 0
+                    },
+                    MemoryOperationDefinition::Access { page: 3 },
+                    MemoryOperationDefinition::Grow { blocks: 4 },
+                    MemoryOperationDefinition::Shrink { blocks: 2 },
+                ]
+            );
+            let errors = parse_str(
+                "p",
+                "2, 1, 3, 8+x+0, 0, 0, 0, 0\n2, 1, 3, 8+4, 0, 0, 0, 0\n2, 1, 3, 8, 0, 0, 0, 0; heap:1 code:y\n",
+            )
+            .err()
+            .unwrap();
+            assert_eq!((errors[0].line, errors[0].column), (1, 12));
+            assert_eq!(
+                errors[0].message,
+                "expected a non-negative integer for `data`, found `x`"
+            );
+            assert_eq!((errors[1].line, errors[1].column), (2, 10));
+            assert_eq!((errors[2].line, errors[2].column), (3, 25));
+        }
+
+        #[test]
+        fn parses_resource_requests() {
+            let definitions =
+                parse_str("p", "2, 1, 3, 8, 0, 1, 0, 0; 0 request:printer 1\n").unwrap();
+            assert_eq!(
+                definitions[0].resource_requests,
+                vec![ResourceRequestDefinition {
+                    resource: Resource::Printer,
+                    after: 1,
+                }]
+            );
+            assert_eq!(definitions[0].memory_operations.len(), 2);
+            let errors = parse_str("p", "2, 1, 3, 8, 0, 1, 0, 0; request:fax\n")
+                .err()
+                .unwrap();
+            assert_eq!((errors[0].line, errors[0].column), (1, 33));
+            assert_eq!(
+                errors[0].message,
+                "unknown resource `fax`, expected one of scanner, printer, modem, sata"
+            );
+        }
+
+        #[test]
+        fn parses_max_claims() {
+            let definitions = parse_str("p", "2, 1, 3, 8, 1, 0, 0, 0; max:printer=2 0\n").unwrap();
+            assert_eq!(definitions[0].max_claims, vec![(Resource::Printer, 2)]);
+            assert_eq!(
+                definitions[0].memory_operations,
+                vec![MemoryOperationDefinition::Access { page: 0 }]
+            );
+            let errors = parse_str("p", "2, 1, 3, 8, 1, 0, 0, 0; max:printer=x max:fax\n")
+                .err()
+                .unwrap();
+            assert_eq!((errors[0].line, errors[0].column), (1, 37));
+            let errors = parse_str("p", "2, 1, 3, 8, 1, 0, 0, 0; max:printer\n")
+                .err()
+                .unwrap();
+            assert_eq!(
+                errors[0].message,
+                "expected `resource=units`, found `printer`"
+            );
+        }
+
+        #[test]
+        fn collects_an_error_per_bad_line() {
+            let errors = parse_str(
+                "p",
+                "2, 0, 3, 64, 0, 0, 0\n2, 0, 3, 64, 0, 0, 0, 0\n2, 0, -3, 64, 0, 0, 0, 0\n",
+            )
+            .err()
+            .unwrap();
+            assert_eq!(errors.len(), 2);
+            assert_eq!((errors[0].line, errors[0].column), (1, 21));
+            assert_eq!((errors[1].line, errors[1].column), (3, 7));
+            assert_eq!(
+                errors[1].message,
+                "expected a non-negative integer for `cpu_time`, found `-3`"
+            );
+        }
     }
-    process_definitions
 }
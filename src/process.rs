@@ -1,4 +1,11 @@
-use crate::{resources::Resource, structures::segment_list::Segment};
+use std::collections::VecDeque;
+
+use crate::{
+    memory::AddressSpace,
+    paging::PageAccess,
+    resources::Resource,
+    segmentation::{ProtectionFault, SegmentKind},
+};
 
 #[derive(Debug, Clone)]
 pub enum DiskOperation {
@@ -7,10 +14,35 @@ pub enum DiskOperation {
 }
 
 #[derive(Debug, Clone)]
+pub enum MemoryOperation {
+    Access { page: usize },
+    SegmentAccess { segment: SegmentKind, offset: usize },
+    Grow { blocks: usize },
+    Shrink { blocks: usize },
+}
+
+/// A unit of `resource` requested after the first `after` memory operations.
+#[derive(Debug, Clone)]
+pub struct ResourceRequest {
+    pub resource: Resource,
+    pub after: usize,
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Interruption {
     None,
-    AllocResource { resource: Resource },
-    DiskInterruption { instruction: DiskOperation },
+    AllocResource {
+        resource: Resource,
+    },
+    DiskInterruption {
+        instruction: DiskOperation,
+    },
+    MemoryInterruption {
+        instruction: MemoryOperation,
+    },
+    /// An access out of the bounds of a segment, which terminates the process.
+    ProtectionFault(ProtectionFault),
     Terminate,
 }
 
@@ -20,23 +52,32 @@ pub struct SoftwareContext {
     pub priority: usize,
     pub files_created: Vec<char>,
     pub resources: Vec<Resource>,
-    cpu_time: usize,
+    /// Most units of each resource, indexed by resource, the process may
+    /// hold at once.
+    pub max_claims: [usize; 4],
+    /// Ticks of the current quantum already used before the process last left the CPU.
+    pub quantum_used: usize,
+    pub cpu_time: usize,
     instructions: Vec<Interruption>,
 }
 
 #[derive(Debug)]
 struct HardwareContext {
     pc: usize,
+    /// Resource requests to make again after a roll back, before going on
+    /// from `pc`.
+    replay: VecDeque<Resource>,
 }
 
 #[derive(Debug)]
 pub struct Process {
     hardware_context: HardwareContext,
     pub software_context: SoftwareContext,
-    pub address_space: Segment,
+    pub address_space: AddressSpace,
 }
 
 impl Process {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: usize,
         priority: usize,
@@ -46,7 +87,9 @@ impl Process {
         use_modem: bool,
         use_sata: bool,
         disk_operations: Vec<DiskOperation>,
-        address_space: Segment,
+        memory_operations: Vec<MemoryOperation>,
+        resource_requests: Vec<ResourceRequest>,
+        address_space: AddressSpace,
     ) -> Process {
         let instructions = Process::build_instructions(
             priority,
@@ -55,9 +98,14 @@ impl Process {
             use_modem,
             use_sata,
             disk_operations,
+            memory_operations,
+            resource_requests,
         );
         Process {
-            hardware_context: HardwareContext { pc: 0 },
+            hardware_context: HardwareContext {
+                pc: 0,
+                replay: VecDeque::new(),
+            },
             software_context: SoftwareContext {
                 id,
                 priority,
@@ -65,11 +113,14 @@ impl Process {
                 instructions,
                 files_created: Vec::new(),
                 resources: Vec::new(),
+                max_claims: [0; 4],
+                quantum_used: 0,
             },
             address_space,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn build_instructions(
         priority: usize,
         use_printer: bool,
@@ -77,9 +128,14 @@ impl Process {
         use_modem: bool,
         use_sata: bool,
         disk_operations: Vec<DiskOperation>,
+        memory_operations: Vec<MemoryOperation>,
+        mut resource_requests: Vec<ResourceRequest>,
     ) -> Vec<Interruption> {
         let mut instructions = Vec::new();
-        if priority != 0 {
+        // Real-time processes cannot use I/O devices.
+        if priority == 0 {
+            resource_requests.clear();
+        } else {
             if use_scanner {
                 instructions.push(Interruption::AllocResource {
                     resource: Resource::Scanner,
@@ -106,10 +162,27 @@ impl Process {
                 instruction: disk_operation,
             });
         }
+        let mut resource_requests = resource_requests.into_iter().peekable();
+        for (index, instruction) in memory_operations.into_iter().enumerate() {
+            while let Some(request) = resource_requests.next_if(|request| request.after <= index) {
+                instructions.push(Interruption::AllocResource {
+                    resource: request.resource,
+                });
+            }
+            instructions.push(Interruption::MemoryInterruption { instruction });
+        }
+        instructions.extend(
+            resource_requests.map(|request| Interruption::AllocResource {
+                resource: request.resource,
+            }),
+        );
         instructions
     }
 
     pub fn on_tick(&mut self) -> Interruption {
+        if let Some(resource) = self.hardware_context.replay.pop_front() {
+            return Interruption::AllocResource { resource };
+        }
         if self.hardware_context.pc
             >= self.software_context.cpu_time + self.software_context.instructions.len()
         {
@@ -124,22 +197,83 @@ impl Process {
         interruption.clone()
     }
 
-    pub fn println(&self) {
-        println!(
-            "{{ pid: {}, offset: {}, blocks: {}, priority: {}, time: {}, instructions: {:?} }}",
-            self.software_context.id,
-            self.address_space.offset,
-            self.address_space.length,
-            self.software_context.priority,
-            self.software_context.cpu_time,
-            self.software_context
-                .instructions
-                .iter()
-                .filter_map(|instruction| match instruction {
-                    Interruption::AllocResource { resource } => Some(resource),
-                    _ => None,
-                })
-                .collect::<Vec<_>>(),
-        )
+    /// Instructions executed so far, CPU ticks included.
+    pub fn executed(&self) -> usize {
+        self.hardware_context.pc
+    }
+
+    /// Makes every resource request made so far again before going on,
+    /// leaving the other instructions, and their effects, as they are.
+    /// The process must have released its resources.
+    pub fn roll_back(&mut self) {
+        let pc = self.hardware_context.pc;
+        self.hardware_context.replay = self.software_context.instructions
+            [..pc.min(self.software_context.instructions.len())]
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Interruption::AllocResource { resource } => Some(*resource),
+                _ => None,
+            })
+            .collect();
+    }
+
+    /// Instructions left to execute, CPU ticks included.
+    pub fn remaining_time(&self) -> usize {
+        (self.software_context.cpu_time + self.software_context.instructions.len())
+            .saturating_sub(self.hardware_context.pc)
+            + self.hardware_context.replay.len()
+    }
+
+    /// Touches `page` of a paged address space.
+    pub fn access_page(&mut self, page: usize) -> PageAccess {
+        let future = self.software_context.instructions[self
+            .hardware_context
+            .pc
+            .min(self.software_context.instructions.len())..]
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Interruption::MemoryInterruption {
+                    instruction: MemoryOperation::Access { page },
+                } => Some(*page),
+                _ => None,
+            })
+            .collect::<Vec<usize>>();
+        match &mut self.address_space {
+            AddressSpace::Paged(page_table) => page_table.access(page, &future),
+            _ => PageAccess::Unmapped,
+        }
+    }
+
+    /// Physical block of `offset` within `segment`, `None` when the address
+    /// space is not translated by segments.
+    pub fn translate(
+        &self,
+        segment: SegmentKind,
+        offset: usize,
+    ) -> Result<Option<usize>, Interruption> {
+        self.address_space
+            .translate(segment, offset)
+            .map_err(Interruption::ProtectionFault)
+    }
+
+    /// Units of each resource, indexed by resource, the process requests.
+    pub fn requested_units(&self) -> [usize; 4] {
+        let mut units = [0; 4];
+        for resource in self.requested_resources() {
+            units[resource as usize] += 1;
+        }
+        units
+    }
+
+    /// Resources the process will request, in request order.
+    pub fn requested_resources(&self) -> Vec<Resource> {
+        self.software_context
+            .instructions
+            .iter()
+            .filter_map(|instruction| match instruction {
+                Interruption::AllocResource { resource } => Some(*resource),
+                _ => None,
+            })
+            .collect()
     }
 }
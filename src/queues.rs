@@ -1,22 +1,41 @@
 use std::collections::VecDeque;
 
+use crate::events::SimEvent;
 use crate::process::Process;
+use crate::scheduler::{EnqueueReason, FeedbackQueues, Scheduler};
 
 pub struct ExecutionContext {
     process: Process,
     start_time: usize,
 }
 
+impl ExecutionContext {
+    /// Ticks of the current quantum used by the process up to `timestamp`,
+    /// including the ones used before it last left the CPU.
+    fn quantum_used(&self, timestamp: usize) -> usize {
+        self.process.software_context.quantum_used + timestamp - self.start_time
+    }
+}
+
+/// Keeps the running process and the ready ones. Real-time processes are
+/// served first, in arrival order, and are never preempted; user processes
+/// are ordered by the configured `Scheduler`.
 pub struct ProcessManager {
     execution: Option<ExecutionContext>,
-    queues: Vec<VecDeque<Process>>,
+    real_time_queue: VecDeque<Process>,
+    scheduler: Box<dyn Scheduler>,
 }
 
 impl ProcessManager {
     pub fn new() -> ProcessManager {
+        ProcessManager::with_scheduler(Box::new(FeedbackQueues::new()))
+    }
+
+    pub fn with_scheduler(scheduler: Box<dyn Scheduler>) -> ProcessManager {
         ProcessManager {
             execution: None,
-            queues: (0..=3).map(|_| VecDeque::new()).collect(),
+            real_time_queue: VecDeque::new(),
+            scheduler,
         }
     }
 
@@ -26,10 +45,17 @@ impl ProcessManager {
             .map(|execution_context| &mut execution_context.process)
     }
 
-    fn fill_executing_context(&mut self, timestamp: usize) {
-        if let None = self.execution {
-            let next_process_option = self.queues.iter_mut().find_map(|queue| queue.pop_front());
+    fn fill_executing_context(&mut self, timestamp: usize, events: &mut Vec<SimEvent>) {
+        if self.execution.is_none() {
+            let next_process_option = self
+                .real_time_queue
+                .pop_front()
+                .or_else(|| self.scheduler.pick_next());
             if let Some(next_process) = next_process_option {
+                events.push(SimEvent::Dispatched {
+                    pid: next_process.software_context.id,
+                    priority: next_process.software_context.priority,
+                });
                 self.execution = Some(ExecutionContext {
                     process: next_process,
                     start_time: timestamp,
@@ -38,53 +64,118 @@ impl ProcessManager {
         }
     }
 
-    pub fn add_process(&mut self, process: Process, timestamp: usize) {
-        let priority = process.software_context.priority;
-        self.queues[priority].push_back(process);
-        self.fill_executing_context(timestamp);
+    fn enqueue(&mut self, process: Process, reason: EnqueueReason, events: &mut Vec<SimEvent>) {
+        let pid = process.software_context.id;
+        let priority = match process.software_context.priority {
+            0 => {
+                self.real_time_queue.push_back(process);
+                0
+            }
+            _ => self.scheduler.enqueue(process, reason),
+        };
+        if matches!(reason, EnqueueReason::Preempted | EnqueueReason::Displaced) {
+            events.push(SimEvent::Preempted { pid, priority });
+        }
+        events.push(SimEvent::Enqueued { pid, priority });
+    }
+
+    pub fn add_process(&mut self, process: Process, timestamp: usize, events: &mut Vec<SimEvent>) {
+        self.enqueue(process, EnqueueReason::Arrived, events);
+        self.fill_executing_context(timestamp, events);
     }
 
-    pub fn block_current_process(&mut self) -> Option<Process> {
-        let process = self.execution.take()?.process;
+    /// Hands back a process that was blocked on a resource or device.
+    pub fn unblock_process(
+        &mut self,
+        process: Process,
+        timestamp: usize,
+        events: &mut Vec<SimEvent>,
+    ) {
+        self.enqueue(process, EnqueueReason::Unblocked, events);
+        self.fill_executing_context(timestamp, events);
+    }
+
+    /// Takes the running process off the CPU during the tick `timestamp`,
+    /// keeping the part of its quantum it already used.
+    pub fn block_current_process(&mut self, timestamp: usize) -> Option<Process> {
+        let execution_context = self.execution.take()?;
+        let quantum_used = execution_context.quantum_used(timestamp + 1);
+        let mut process = execution_context.process;
+        process.software_context.quantum_used = quantum_used;
         Some(process)
     }
 
-    pub fn terminate_current_process(&mut self) -> Option<Process> {
+    pub fn terminate_current_process(&mut self, events: &mut Vec<SimEvent>) -> Option<Process> {
         let process = self.execution.take()?.process;
-        println!("Terminating process {}", process.software_context.id);
+        events.push(SimEvent::Terminated {
+            pid: process.software_context.id,
+            priority: process.software_context.priority,
+            address_space: process.address_space.clone(),
+        });
         Some(process)
     }
 
-    pub fn on_tick(&mut self, timestamp: usize) {
-        const USER_PROCESS_QUANTUM: usize = 1;
-        let should_change_context = match self.execution.as_ref() {
-            None => true,
+    pub fn on_tick(&mut self, timestamp: usize, events: &mut Vec<SimEvent>) {
+        self.scheduler.on_tick(timestamp, events);
+        let preemption = match self.execution.as_ref() {
+            None => None,
+            // An expired quantum is spent even when a real-time process
+            // takes the CPU, so that the process is still demoted.
             Some(execution_context) => match execution_context.process.software_context.priority {
-                0 => false,
-                _ => timestamp - execution_context.start_time >= USER_PROCESS_QUANTUM,
+                0 => None,
+                _ if self.scheduler.should_preempt(
+                    &execution_context.process,
+                    execution_context.quantum_used(timestamp),
+                ) =>
+                {
+                    Some(EnqueueReason::Preempted)
+                }
+                _ if !self.real_time_queue.is_empty() => Some(EnqueueReason::Displaced),
+                _ => None,
             },
         };
-        if should_change_context {
-            match self.execution.take() {
-                None => {}
-                Some(execution_context) => {
-                    let mut process = execution_context.process;
-                    process.software_context.priority =
-                        std::cmp::min(process.software_context.priority + 1, 3);
-                    self.add_process(process, timestamp);
-                }
+        if let Some(reason) = preemption {
+            if let Some(execution_context) = self.execution.take() {
+                let quantum_used = match reason {
+                    EnqueueReason::Preempted => 0,
+                    _ => execution_context.quantum_used(timestamp),
+                };
+                let mut process = execution_context.process;
+                process.software_context.quantum_used = quantum_used;
+                self.enqueue(process, reason, events);
             }
-            self.fill_executing_context(timestamp)
         }
+        self.fill_executing_context(timestamp, events)
+    }
+
+    /// The running process and every ready one.
+    pub fn processes_mut(&mut self) -> Vec<&mut Process> {
+        let mut processes = self.scheduler.processes_mut();
+        processes.extend(self.real_time_queue.iter_mut());
+        processes.extend(
+            self.execution
+                .as_mut()
+                .map(|execution_context| &mut execution_context.process),
+        );
+        processes
     }
 
     pub fn has_more_processes(&self) -> bool {
-        self.queues.iter().any(|queue| !queue.is_empty()) || self.execution.is_some()
+        !self.real_time_queue.is_empty()
+            || self.scheduler.has_processes()
+            || self.execution.is_some()
+    }
+}
+
+impl Default for ProcessManager {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::memory::AddressSpace;
     use crate::structures::segment_list::Segment;
 
     use super::*;
@@ -99,10 +190,12 @@ mod tests {
             false,
             false,
             vec![],
-            Segment {
+            vec![],
+            vec![],
+            AddressSpace::Contiguous(Segment {
                 offset: 0,
                 length: 0,
-            },
+            }),
         )
     }
 
@@ -111,7 +204,7 @@ mod tests {
         let mut process_manager = ProcessManager::new();
         let process = create_process_mock(0);
         assert!(process_manager.get_current_process().is_none());
-        process_manager.add_process(process, 0);
+        process_manager.add_process(process, 0, &mut vec![]);
         assert!(process_manager.get_current_process().is_some());
     }
 
@@ -119,15 +212,15 @@ mod tests {
     fn test_block_current_process() {
         let mut process_manager = ProcessManager::new();
         let process0 = create_process_mock(0);
-        process_manager.add_process(process0, 0);
+        process_manager.add_process(process0, 0, &mut vec![]);
         let process1 = create_process_mock(1);
-        process_manager.add_process(process1, 0);
+        process_manager.add_process(process1, 0, &mut vec![]);
         assert!(process_manager.get_current_process().is_some());
-        process_manager.block_current_process();
-        process_manager.on_tick(1);
+        process_manager.block_current_process(0);
+        process_manager.on_tick(1, &mut vec![]);
         assert!(process_manager.get_current_process().is_some());
-        process_manager.block_current_process();
-        process_manager.on_tick(2);
+        process_manager.block_current_process(1);
+        process_manager.on_tick(2, &mut vec![]);
         assert!(process_manager.get_current_process().is_none());
     }
 
@@ -136,8 +229,8 @@ mod tests {
         let mut process_manager = ProcessManager::new();
         let real_time_process = create_process_mock(0);
         let user_process = create_process_mock(1);
-        process_manager.add_process(real_time_process, 0);
-        process_manager.add_process(user_process, 0);
+        process_manager.add_process(real_time_process, 0, &mut vec![]);
+        process_manager.add_process(user_process, 0, &mut vec![]);
         assert!(
             process_manager
                 .get_current_process()
@@ -146,7 +239,7 @@ mod tests {
                 .priority
                 == 0
         );
-        process_manager.on_tick(1);
+        process_manager.on_tick(1, &mut vec![]);
         assert!(
             process_manager
                 .get_current_process()
@@ -156,9 +249,9 @@ mod tests {
                 == 0
         );
 
-        let real_time_process = process_manager.block_current_process().unwrap();
-        process_manager.on_tick(2);
-        process_manager.add_process(real_time_process, 1);
+        let real_time_process = process_manager.block_current_process(1).unwrap();
+        process_manager.on_tick(2, &mut vec![]);
+        process_manager.add_process(real_time_process, 1, &mut vec![]);
         assert_eq!(
             process_manager
                 .get_current_process()
@@ -167,7 +260,56 @@ mod tests {
                 .priority,
             1
         );
-        process_manager.on_tick(3);
+        process_manager.on_tick(3, &mut vec![]);
+        assert_eq!(
+            process_manager
+                .get_current_process()
+                .unwrap()
+                .software_context
+                .priority,
+            0
+        );
+    }
+
+    #[test]
+    fn blocked_process_keeps_its_partial_quantum() {
+        use crate::scheduler::{AgingPolicy, FeedbackQueues};
+
+        let mut process_manager = ProcessManager::with_scheduler(Box::new(
+            FeedbackQueues::with_options(vec![3, 3, 3], AgingPolicy::default()),
+        ));
+        process_manager.add_process(create_process_mock(1), 0, &mut vec![]);
+        process_manager.on_tick(1, &mut vec![]);
+        let process = process_manager.block_current_process(1).unwrap();
+        assert_eq!(process.software_context.quantum_used, 2);
+        process_manager.unblock_process(process, 2, &mut vec![]);
+        process_manager.on_tick(3, &mut vec![]);
+        assert_eq!(
+            process_manager
+                .get_current_process()
+                .unwrap()
+                .software_context
+                .priority,
+            2
+        );
+    }
+
+    #[test]
+    fn quantum_expiry_demotes_a_displaced_process() {
+        use crate::scheduler::{AgingPolicy, FeedbackQueues};
+
+        let mut process_manager = ProcessManager::with_scheduler(Box::new(
+            FeedbackQueues::with_options(vec![2, 2, 2], AgingPolicy::default()),
+        ));
+        process_manager.add_process(create_process_mock(1), 0, &mut vec![]);
+        process_manager.on_tick(1, &mut vec![]);
+        process_manager.add_process(create_process_mock(0), 1, &mut vec![]);
+        let mut events = vec![];
+        process_manager.on_tick(2, &mut events);
+        assert!(events.contains(&SimEvent::Preempted {
+            pid: 0,
+            priority: 2
+        }));
         assert_eq!(
             process_manager
                 .get_current_process()
@@ -176,5 +318,11 @@ mod tests {
                 .priority,
             0
         );
+        let user_process = process_manager
+            .processes_mut()
+            .into_iter()
+            .find(|process| process.software_context.priority == 2)
+            .unwrap();
+        assert_eq!(user_process.software_context.quantum_used, 0);
     }
 }
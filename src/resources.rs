@@ -1,83 +1,516 @@
 mod resource_mutex;
 
+use std::cmp::Reverse;
+use std::str::FromStr;
+
+use crate::config::ResourceConfig;
+use crate::events::SimEvent;
 use crate::process::Process;
 
 pub use self::resource_mutex::Resource;
 use self::resource_mutex::ResourceMutex;
 
+/// What is done with the processes of a deadlock once it is detected.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum DeadlockRecovery {
+    /// The deadlock is only reported.
+    #[default]
+    None,
+    /// Every deadlocked process is terminated.
+    AbortAll,
+    /// Victims are terminated one at a time until the deadlock is broken.
+    AbortOne,
+    /// Victims give back their resources one at a time and are rolled back
+    /// to request them again.
+    Preempt,
+}
+
+impl FromStr for DeadlockRecovery {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "none" => Ok(DeadlockRecovery::None),
+            "abort-all" => Ok(DeadlockRecovery::AbortAll),
+            "abort-one" => Ok(DeadlockRecovery::AbortOne),
+            "preempt" => Ok(DeadlockRecovery::Preempt),
+            _ => Err(format!(
+                "unknown deadlock recovery `{}`, expected one of none, abort-all, abort-one, preempt",
+                name
+            )),
+        }
+    }
+}
+
+/// How the victim of a deadlock is chosen; ties go to the latest pid.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum VictimSelection {
+    /// The process of the lowest priority, the highest queue number.
+    #[default]
+    LowestPriority,
+    /// The process that executed the fewest instructions.
+    LeastCpu,
+    /// The process holding the fewest resource units.
+    FewestResources,
+}
+
+impl FromStr for VictimSelection {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "lowest-priority" => Ok(VictimSelection::LowestPriority),
+            "least-cpu" => Ok(VictimSelection::LeastCpu),
+            "fewest-resources" => Ok(VictimSelection::FewestResources),
+            _ => Err(format!(
+                "unknown deadlock victim `{}`, expected one of lowest-priority, least-cpu, fewest-resources",
+                name
+            )),
+        }
+    }
+}
+
 pub struct ResourceManager {
     resource_mutex_vec: Vec<ResourceMutex>,
+    recovery: DeadlockRecovery,
+    victim: VictimSelection,
+    /// Only grant requests that leave the system in a safe state.
+    avoidance: bool,
+    /// Claims of the admitted processes, with avoidance.
+    claims: Vec<Claim>,
+    /// Report the safe sequence behind every grant.
+    verbose: bool,
+}
+
+/// Maximum claim and units held of a process, indexed by resource.
+struct Claim {
+    pid: usize,
+    maximum: [usize; 4],
+    allocation: [usize; 4],
+}
+
+/// Processes blocked on resources that none of them can ever get, and the
+/// resources they hold or wait for.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Deadlock {
+    pub pids: Vec<usize>,
+    pub resources: Vec<Resource>,
 }
 
 impl ResourceManager {
-    pub fn new() -> Self {
+    pub fn new(config: &ResourceConfig) -> Self {
         ResourceManager {
             resource_mutex_vec: {
                 let mut vec = Vec::new();
                 vec.insert(
                     Resource::Scanner as usize,
-                    ResourceMutex::new(vec![Resource::Scanner; 1]),
+                    ResourceMutex::new(vec![Resource::Scanner; config.scanners]),
                 );
                 vec.insert(
                     Resource::Printer as usize,
-                    ResourceMutex::new(vec![Resource::Printer; 2]),
+                    ResourceMutex::new(vec![Resource::Printer; config.printers]),
                 );
                 vec.insert(
                     Resource::Modem as usize,
-                    ResourceMutex::new(vec![Resource::Modem; 1]),
+                    ResourceMutex::new(vec![Resource::Modem; config.modems]),
                 );
                 vec.insert(
                     Resource::SataDevice as usize,
-                    ResourceMutex::new(vec![Resource::SataDevice; 2]),
+                    ResourceMutex::new(vec![Resource::SataDevice; config.sata_devices]),
                 );
                 vec
             },
+            recovery: config.deadlock_recovery,
+            victim: config.deadlock_victim,
+            avoidance: config.deadlock_avoidance,
+            claims: Vec::new(),
+            verbose: false,
         }
     }
 
-    pub fn request(&mut self, process: Process, resource: Resource) -> Option<Process> {
-        let process_id = process.software_context.id;
+    /// Makes every grant with deadlock avoidance report its safe sequence.
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    pub fn recovery(&self) -> DeadlockRecovery {
+        self.recovery
+    }
+
+    /// Registers the claim of a newly admitted process, with avoidance, so
+    /// that it counts in every safety check until the process terminates.
+    pub fn register_claim(&mut self, process: &Process) {
+        if self.avoidance {
+            self.claims.push(Claim {
+                pid: process.software_context.id,
+                maximum: process.software_context.max_claims,
+                allocation: [0; 4],
+            });
+        }
+    }
+
+    /// Drops the claim of a terminated or aborted process.
+    pub fn withdraw_claim(&mut self, pid: usize) {
+        self.claims.retain(|claim| claim.pid != pid);
+    }
+
+    /// Grants `resource` to `process` or blocks it. With avoidance, a
+    /// request above the claim of the process is denied and the process
+    /// handed back without the unit.
+    pub fn request(
+        &mut self,
+        process: Process,
+        resource: Resource,
+        events: &mut Vec<SimEvent>,
+    ) -> Option<Process> {
+        let pid = process.software_context.id;
+        let priority = process.software_context.priority;
+        let safe_sequence = match self.avoidance {
+            true => {
+                let remaining = self
+                    .claims
+                    .iter()
+                    .find(|claim| claim.pid == pid)
+                    .map_or(0, |claim| {
+                        claim.maximum[resource as usize] - claim.allocation[resource as usize]
+                    });
+                if remaining == 0 {
+                    events.push(SimEvent::ClaimExceeded {
+                        pid,
+                        priority,
+                        resource,
+                    });
+                    return Some(process);
+                }
+                let Some(safe_sequence) = self.safe_sequence(pid, resource) else {
+                    self.resource_mutex_vec[resource as usize].enqueue(process);
+                    events.push(SimEvent::Blocked {
+                        pid,
+                        priority,
+                        resource,
+                    });
+                    return None;
+                };
+                Some(safe_sequence)
+            }
+            false => None,
+        };
         let resource_mutex = &mut self.resource_mutex_vec[resource as usize];
         match resource_mutex.request(process) {
             None => {
-                println!(
-                    "Process {} blocked waiting for resource {:?}\n",
-                    process_id, resource
-                );
+                events.push(SimEvent::Blocked {
+                    pid,
+                    priority,
+                    resource,
+                });
+                // A new deadlock can only form when a process blocks, and
+                // always includes it.
+                if let Some(deadlock) = self
+                    .detect_deadlock()
+                    .filter(|deadlock| deadlock.pids.contains(&pid))
+                {
+                    events.push(SimEvent::Deadlocked {
+                        pids: deadlock.pids,
+                        resources: deadlock.resources,
+                    });
+                }
                 None
             }
             Some((resource, mut process)) => {
-                println!("Process {} allocated resource {:?}\n", process_id, resource);
+                events.push(SimEvent::ResourceGranted {
+                    pid,
+                    priority,
+                    resource,
+                });
+                self.granted(pid, resource, safe_sequence, events);
                 process.software_context.resources.push(resource);
                 Some(process)
             }
         }
     }
 
-    pub fn release_resources(&mut self, process: &mut Process) -> Vec<Process> {
+    /// Records a unit of `resource` given to `pid` with avoidance.
+    fn granted(
+        &mut self,
+        pid: usize,
+        resource: Resource,
+        safe_sequence: Option<Vec<usize>>,
+        events: &mut Vec<SimEvent>,
+    ) {
+        if let Some(claim) = self.claims.iter_mut().find(|claim| claim.pid == pid) {
+            claim.allocation[resource as usize] += 1;
+        }
+        if let Some(sequence) = safe_sequence.filter(|_| self.verbose) {
+            events.push(SimEvent::SafeSequence {
+                pid,
+                resource,
+                sequence,
+            });
+        }
+    }
+
+    /// Banker's algorithm: the order the claiming processes could terminate
+    /// in if `pid` got a unit of `resource`, each getting its whole claim
+    /// from the units the previous ones gave back. `None` when there is no
+    /// free unit or some process could not terminate.
+    fn safe_sequence(&self, pid: usize, resource: Resource) -> Option<Vec<usize>> {
+        let mut available = self
+            .resource_mutex_vec
+            .iter()
+            .map(ResourceMutex::available)
+            .collect::<Vec<_>>();
+        available[resource as usize] = available[resource as usize].checked_sub(1)?;
+        let allocations = self
+            .claims
+            .iter()
+            .map(|claim| {
+                let mut allocation = claim.allocation;
+                if claim.pid == pid {
+                    allocation[resource as usize] += 1;
+                }
+                allocation
+            })
+            .collect::<Vec<_>>();
+        let mut sequence = Vec::new();
+        let mut finished = vec![false; self.claims.len()];
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for (index, claim) in self.claims.iter().enumerate() {
+                let allocation = &allocations[index];
+                let fits = (0..available.len())
+                    .all(|class| claim.maximum[class] - allocation[class] <= available[class]);
+                if finished[index] || !fits {
+                    continue;
+                }
+                for (class, units) in allocation.iter().enumerate() {
+                    available[class] += units;
+                }
+                finished[index] = true;
+                sequence.push(claim.pid);
+                progress = true;
+            }
+        }
+        finished
+            .iter()
+            .all(|finished| *finished)
+            .then_some(sequence)
+    }
+
+    /// Gives the free units to the waiting processes that can safely get
+    /// them, in queue order, with avoidance.
+    fn grant_waiting(&mut self, events: &mut Vec<SimEvent>) -> Vec<Process> {
+        let mut unblocked_processes = Vec::new();
+        loop {
+            let next = self
+                .resource_mutex_vec
+                .iter()
+                .zip(Resource::ALL)
+                .flat_map(|(resource_mutex, resource)| {
+                    resource_mutex
+                        .queue()
+                        .map(move |process| (resource, process.software_context.id))
+                })
+                .find_map(|(resource, pid)| Some((pid, self.safe_sequence(pid, resource)?)));
+            let Some((pid, safe_sequence)) = next else {
+                return unblocked_processes;
+            };
+            let Some((resource, mut process)) = self
+                .resource_mutex_vec
+                .iter_mut()
+                .find_map(|resource_mutex| resource_mutex.grant(pid))
+            else {
+                return unblocked_processes;
+            };
+            events.push(SimEvent::Unblocked {
+                pid,
+                priority: process.software_context.priority,
+                resource,
+            });
+            self.granted(pid, resource, Some(safe_sequence), events);
+            process.software_context.resources.push(resource);
+            unblocked_processes.push(process);
+        }
+    }
+
+    /// Reduces the resource allocation graph: processes that are not blocked
+    /// on a resource will terminate and give back their units, and so will
+    /// every blocked process whose resource has a unit to spare once they
+    /// did. The blocked processes left can never be unblocked.
+    pub fn detect_deadlock(&self) -> Option<Deadlock> {
+        let blocked = self
+            .resource_mutex_vec
+            .iter()
+            .enumerate()
+            .flat_map(|(index, resource_mutex)| {
+                resource_mutex.queue().map(move |process| (index, process))
+            })
+            .collect::<Vec<_>>();
+        let mut available = self
+            .resource_mutex_vec
+            .iter()
+            .map(ResourceMutex::units)
+            .collect::<Vec<_>>();
+        for (_, process) in &blocked {
+            for resource in &process.software_context.resources {
+                available[*resource as usize] -= 1;
+            }
+        }
+        let mut finished = vec![false; blocked.len()];
+        let mut progress = true;
+        while progress {
+            progress = false;
+            for (index, (waited, process)) in blocked.iter().enumerate() {
+                if finished[index] || available[*waited] == 0 {
+                    continue;
+                }
+                for resource in &process.software_context.resources {
+                    available[*resource as usize] += 1;
+                }
+                finished[index] = true;
+                progress = true;
+            }
+        }
+        let deadlocked = blocked
+            .iter()
+            .zip(finished)
+            .filter(|(_, finished)| !finished)
+            .map(|(blocked, _)| *blocked)
+            .collect::<Vec<_>>();
+        if deadlocked.is_empty() {
+            return None;
+        }
+        let mut pids = deadlocked
+            .iter()
+            .map(|(_, process)| process.software_context.id)
+            .collect::<Vec<_>>();
+        pids.sort();
+        let mut involved = vec![false; self.resource_mutex_vec.len()];
+        for (waited, process) in &deadlocked {
+            involved[*waited] = true;
+            for resource in &process.software_context.resources {
+                involved[*resource as usize] = true;
+            }
+        }
+        let resources = Resource::ALL
+            .into_iter()
+            .filter(|resource| involved[*resource as usize])
+            .collect();
+        Some(Deadlock { pids, resources })
+    }
+
+    /// Processes to act on to break the current deadlock, according to the
+    /// recovery policy: all of them or a single victim.
+    pub fn deadlock_victims(&self) -> Vec<usize> {
+        if self.recovery == DeadlockRecovery::None {
+            return vec![];
+        }
+        let Some(deadlock) = self.detect_deadlock() else {
+            return vec![];
+        };
+        if self.recovery == DeadlockRecovery::AbortAll {
+            return deadlock.pids;
+        }
+        let candidates = self
+            .resource_mutex_vec
+            .iter()
+            .flat_map(ResourceMutex::queue)
+            .filter(|process| deadlock.pids.contains(&process.software_context.id));
+        let victim = match self.victim {
+            VictimSelection::LowestPriority => candidates.max_by_key(|process| {
+                (
+                    process.software_context.priority,
+                    process.software_context.id,
+                )
+            }),
+            VictimSelection::LeastCpu => candidates
+                .min_by_key(|process| (process.executed(), Reverse(process.software_context.id))),
+            VictimSelection::FewestResources => candidates.min_by_key(|process| {
+                (
+                    process.software_context.resources.len(),
+                    Reverse(process.software_context.id),
+                )
+            }),
+        };
+        victim
+            .map(|process| process.software_context.id)
+            .into_iter()
+            .collect()
+    }
+
+    /// Takes the process `pid` out of the queue it is blocked in.
+    pub fn take_blocked(&mut self, pid: usize) -> Option<Process> {
+        self.resource_mutex_vec
+            .iter_mut()
+            .find_map(|resource_mutex| resource_mutex.remove(pid))
+    }
+
+    /// Every process blocked waiting for a resource.
+    pub fn blocked_processes(&self) -> impl Iterator<Item = &Process> {
+        self.resource_mutex_vec
+            .iter()
+            .flat_map(ResourceMutex::queue)
+    }
+
+    /// Every process blocked waiting for a resource.
+    pub fn blocked_processes_mut(&mut self) -> impl Iterator<Item = &mut Process> {
+        self.resource_mutex_vec
+            .iter_mut()
+            .flat_map(|resource_mutex| resource_mutex.queue_mut())
+    }
+
+    pub fn release_resources(
+        &mut self,
+        process: &mut Process,
+        events: &mut Vec<SimEvent>,
+    ) -> Vec<Process> {
+        if self.avoidance {
+            let pid = process.software_context.id;
+            for free_resource in process.software_context.resources.drain(..) {
+                events.push(SimEvent::ResourceReleased {
+                    pid,
+                    resource: free_resource,
+                });
+                self.resource_mutex_vec[free_resource as usize].put_back(free_resource);
+            }
+            if let Some(claim) = self.claims.iter_mut().find(|claim| claim.pid == pid) {
+                claim.allocation = [0; 4];
+            }
+            return self.grant_waiting(events);
+        }
         let mut unblocked_processes = Vec::new();
         for free_resource in process.software_context.resources.iter() {
-            println!(
-                "Process {} releasing resource {:?}",
-                process.software_context.id, free_resource
-            );
+            events.push(SimEvent::ResourceReleased {
+                pid: process.software_context.id,
+                resource: *free_resource,
+            });
             match self.resource_mutex_vec[*free_resource as usize].release(*free_resource) {
                 None => (),
                 Some((resource, mut process)) => {
-                    println!("Process {} unblocked", process.software_context.id);
+                    events.push(SimEvent::Unblocked {
+                        pid: process.software_context.id,
+                        priority: process.software_context.priority,
+                        resource,
+                    });
                     process.software_context.resources.push(resource);
                     unblocked_processes.push(process);
                 }
             }
         }
         process.software_context.resources.clear();
-        println!("");
         unblocked_processes
     }
 }
 
+impl Default for ResourceManager {
+    fn default() -> Self {
+        Self::new(&ResourceConfig::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::memory::AddressSpace;
     use crate::structures::segment_list::Segment;
 
     use super::*;
@@ -92,10 +525,12 @@ mod tests {
             false,
             false,
             vec![],
-            Segment {
+            vec![],
+            vec![],
+            AddressSpace::Contiguous(Segment {
                 offset: 0,
                 length: 0,
-            },
+            }),
         )
     }
 
@@ -104,20 +539,20 @@ mod tests {
 
         #[test]
         fn return_process_when_resource_is_available() {
-            let mut resource_manager = ResourceManager::new();
+            let mut resource_manager = ResourceManager::default();
             let process = create_process_mock();
-            let result = resource_manager.request(process, Resource::Scanner);
+            let result = resource_manager.request(process, Resource::Scanner, &mut vec![]);
             assert!(result.is_some());
         }
 
         #[test]
         fn return_none_when_resource_is_not_available() {
-            let mut resource_manager = ResourceManager::new();
+            let mut resource_manager = ResourceManager::default();
             let process = create_process_mock();
-            let result = resource_manager.request(process, Resource::Scanner);
+            let result = resource_manager.request(process, Resource::Scanner, &mut vec![]);
             assert!(result.is_some());
             let process = create_process_mock();
-            let result = resource_manager.request(process, Resource::Scanner);
+            let result = resource_manager.request(process, Resource::Scanner, &mut vec![]);
             assert!(result.is_none());
         }
     }
@@ -127,20 +562,276 @@ mod tests {
 
         #[test]
         fn return_unblocked_processes() {
-            let mut resource_manager = ResourceManager::new();
+            let mut resource_manager = ResourceManager::default();
             let greedy_process = create_process_mock();
             let greedy_process = resource_manager
-                .request(greedy_process, Resource::Scanner)
+                .request(greedy_process, Resource::Scanner, &mut vec![])
                 .unwrap();
             let mut greedy_process = resource_manager
-                .request(greedy_process, Resource::Modem)
+                .request(greedy_process, Resource::Modem, &mut vec![])
                 .unwrap();
             let scanner_process = create_process_mock();
-            resource_manager.request(scanner_process, Resource::Scanner);
+            resource_manager.request(scanner_process, Resource::Scanner, &mut vec![]);
             let modem_process = create_process_mock();
-            resource_manager.request(modem_process, Resource::Modem);
-            let unblocked_processes = resource_manager.release_resources(&mut greedy_process);
+            resource_manager.request(modem_process, Resource::Modem, &mut vec![]);
+            let unblocked_processes =
+                resource_manager.release_resources(&mut greedy_process, &mut vec![]);
             assert_eq!(unblocked_processes.len(), 2);
         }
     }
+
+    mod detect_deadlock {
+        use super::*;
+
+        fn create_process(id: usize) -> Process {
+            let mut process = create_process_mock();
+            process.software_context.id = id;
+            process
+        }
+
+        #[test]
+        fn reports_processes_waiting_for_each_other() {
+            let mut resource_manager = ResourceManager::default();
+            let first = resource_manager
+                .request(create_process(1), Resource::Scanner, &mut vec![])
+                .unwrap();
+            let second = resource_manager
+                .request(create_process(2), Resource::Modem, &mut vec![])
+                .unwrap();
+            let mut events = vec![];
+            resource_manager.request(first, Resource::Modem, &mut events);
+            assert!(resource_manager.detect_deadlock().is_none());
+            resource_manager.request(second, Resource::Scanner, &mut events);
+            assert_eq!(
+                events.last(),
+                Some(&SimEvent::Deadlocked {
+                    pids: vec![1, 2],
+                    resources: vec![Resource::Scanner, Resource::Modem],
+                })
+            );
+        }
+
+        #[test]
+        fn counts_units_held_by_running_processes_as_coming_back() {
+            let mut resource_manager = ResourceManager::default();
+            let first = resource_manager
+                .request(create_process(1), Resource::Scanner, &mut vec![])
+                .unwrap();
+            let second = resource_manager
+                .request(create_process(2), Resource::Printer, &mut vec![])
+                .unwrap();
+            resource_manager.request(create_process(3), Resource::Printer, &mut vec![]);
+            resource_manager.request(first, Resource::Printer, &mut vec![]);
+            // The process holding a printer is not blocked, so the second
+            // printer unblocks the first process, whose scanner unblocks the
+            // second one.
+            resource_manager.request(second, Resource::Scanner, &mut vec![]);
+            assert_eq!(resource_manager.detect_deadlock(), None);
+        }
+    }
+
+    mod deadlock_victims {
+        use super::*;
+
+        /// Processes 1 and 2 deadlocked over the scanner and the modem, the
+        /// first one also holding a printer.
+        fn create_deadlock(recovery: DeadlockRecovery, victim: VictimSelection) -> ResourceManager {
+            let mut resource_manager = ResourceManager::new(&ResourceConfig {
+                deadlock_recovery: recovery,
+                deadlock_victim: victim,
+                ..ResourceConfig::default()
+            });
+            let mut first = create_process_mock();
+            first.software_context.id = 1;
+            first.software_context.priority = 2;
+            let first = resource_manager
+                .request(first, Resource::Scanner, &mut vec![])
+                .unwrap();
+            let mut first = resource_manager
+                .request(first, Resource::Printer, &mut vec![])
+                .unwrap();
+            first.on_tick();
+            let mut second = create_process_mock();
+            second.software_context.id = 2;
+            let second = resource_manager
+                .request(second, Resource::Modem, &mut vec![])
+                .unwrap();
+            resource_manager.request(first, Resource::Modem, &mut vec![]);
+            resource_manager.request(second, Resource::Scanner, &mut vec![]);
+            resource_manager
+        }
+
+        #[test]
+        fn picks_victims_by_policy() {
+            let victims = |recovery, victim| create_deadlock(recovery, victim).deadlock_victims();
+            assert_eq!(
+                victims(DeadlockRecovery::None, VictimSelection::LowestPriority),
+                vec![]
+            );
+            assert_eq!(
+                victims(DeadlockRecovery::AbortAll, VictimSelection::LowestPriority),
+                vec![1, 2]
+            );
+            assert_eq!(
+                victims(DeadlockRecovery::AbortOne, VictimSelection::LowestPriority),
+                vec![1]
+            );
+            assert_eq!(
+                victims(DeadlockRecovery::Preempt, VictimSelection::LeastCpu),
+                vec![2]
+            );
+            assert_eq!(
+                victims(DeadlockRecovery::AbortOne, VictimSelection::FewestResources),
+                vec![2]
+            );
+        }
+
+        #[test]
+        fn takes_victims_out_of_their_queue() {
+            let mut resource_manager =
+                create_deadlock(DeadlockRecovery::AbortOne, VictimSelection::LowestPriority);
+            let mut victim = resource_manager.take_blocked(1).unwrap();
+            assert_eq!(resource_manager.detect_deadlock(), None);
+            let unblocked = resource_manager.release_resources(&mut victim, &mut vec![]);
+            assert_eq!(unblocked[0].software_context.id, 2);
+        }
+    }
+
+    mod avoidance {
+        use super::*;
+
+        fn create_process(id: usize, max_claims: [usize; 4]) -> Process {
+            let mut process = create_process_mock();
+            process.software_context.id = id;
+            process.software_context.max_claims = max_claims;
+            process
+        }
+
+        fn create_resource_manager(claims: &[[usize; 4]]) -> ResourceManager {
+            let mut resource_manager = ResourceManager::new(&ResourceConfig {
+                deadlock_avoidance: true,
+                ..ResourceConfig::default()
+            });
+            for (id, max_claims) in claims.iter().enumerate() {
+                resource_manager.register_claim(&create_process(id + 1, *max_claims));
+            }
+            resource_manager
+        }
+
+        #[test]
+        fn blocks_requests_leading_to_unsafe_states() {
+            // Both claim the scanner and the modem.
+            let mut resource_manager = create_resource_manager(&[[1, 0, 1, 0], [1, 0, 1, 0]]);
+            resource_manager.set_verbose(true);
+            let mut first = resource_manager
+                .request(
+                    create_process(1, [1, 0, 1, 0]),
+                    Resource::Scanner,
+                    &mut vec![],
+                )
+                .unwrap();
+            let mut events = vec![];
+            assert!(resource_manager
+                .request(
+                    create_process(2, [1, 0, 1, 0]),
+                    Resource::Modem,
+                    &mut events
+                )
+                .is_none());
+            assert_eq!(
+                events,
+                vec![SimEvent::Blocked {
+                    pid: 2,
+                    priority: 1,
+                    resource: Resource::Modem
+                }]
+            );
+            resource_manager.withdraw_claim(1);
+            let unblocked = resource_manager.release_resources(&mut first, &mut events);
+            assert_eq!(unblocked[0].software_context.id, 2);
+            assert_eq!(
+                events.last(),
+                Some(&SimEvent::SafeSequence {
+                    pid: 2,
+                    resource: Resource::Modem,
+                    sequence: vec![2]
+                })
+            );
+        }
+
+        #[test]
+        fn grants_requests_within_the_remaining_units() {
+            let mut resource_manager = create_resource_manager(&[[0, 1, 0, 0], [0, 1, 0, 0]]);
+            let first = resource_manager.request(
+                create_process(1, [0, 1, 0, 0]),
+                Resource::Printer,
+                &mut vec![],
+            );
+            let second = resource_manager.request(
+                create_process(2, [0, 1, 0, 0]),
+                Resource::Printer,
+                &mut vec![],
+            );
+            assert!(first.is_some() && second.is_some());
+        }
+
+        #[test]
+        fn counts_admitted_processes_holding_nothing() {
+            let mut resource_manager = create_resource_manager(&[[1, 0, 0, 0], [1, 0, 0, 0]]);
+            resource_manager.set_verbose(true);
+            let mut events = vec![];
+            resource_manager.request(
+                create_process(1, [1, 0, 0, 0]),
+                Resource::Scanner,
+                &mut events,
+            );
+            assert_eq!(
+                events.last(),
+                Some(&SimEvent::SafeSequence {
+                    pid: 1,
+                    resource: Resource::Scanner,
+                    sequence: vec![1, 2]
+                })
+            );
+        }
+
+        #[test]
+        fn denies_requests_beyond_the_claim() {
+            let mut resource_manager = create_resource_manager(&[[1, 0, 0, 0]]);
+            let process = resource_manager
+                .request(
+                    create_process(1, [1, 0, 0, 0]),
+                    Resource::Scanner,
+                    &mut vec![],
+                )
+                .unwrap();
+            let mut events = vec![];
+            let process = resource_manager
+                .request(process, Resource::Scanner, &mut events)
+                .unwrap();
+            assert_eq!(process.software_context.resources, vec![Resource::Scanner]);
+            assert_eq!(
+                events,
+                vec![SimEvent::ClaimExceeded {
+                    pid: 1,
+                    priority: 1,
+                    resource: Resource::Scanner
+                }]
+            );
+        }
+    }
+
+    mod blocked_processes_mut {
+        use super::*;
+
+        #[test]
+        fn yields_processes_of_every_queue() {
+            let mut resource_manager = ResourceManager::default();
+            for resource in [Resource::Scanner, Resource::Scanner, Resource::Modem] {
+                resource_manager.request(create_process_mock(), resource, &mut vec![]);
+            }
+            resource_manager.request(create_process_mock(), Resource::Modem, &mut vec![]);
+            assert_eq!(resource_manager.blocked_processes_mut().count(), 2);
+        }
+    }
 }
@@ -1,4 +1,5 @@
 use std::collections::VecDeque;
+use std::str::FromStr;
 
 use crate::process::Process;
 
@@ -10,19 +11,90 @@ pub enum Resource {
     SataDevice,
 }
 
+impl Resource {
+    /// Every resource class, in the order processes request them.
+    pub const ALL: [Resource; 4] = [
+        Resource::Scanner,
+        Resource::Printer,
+        Resource::Modem,
+        Resource::SataDevice,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resource::Scanner => "scanner",
+            Resource::Printer => "printer",
+            Resource::Modem => "modem",
+            Resource::SataDevice => "sata",
+        }
+    }
+}
+
+impl FromStr for Resource {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Resource::ALL
+            .into_iter()
+            .find(|resource| resource.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown resource `{}`, expected one of scanner, printer, modem, sata",
+                    name
+                )
+            })
+    }
+}
+
 pub struct ResourceMutex {
     resources: Vec<Resource>,
     queue: VecDeque<Process>,
+    /// Units of the resource, held or not.
+    units: usize,
 }
 
 impl ResourceMutex {
     pub fn new(resources: Vec<Resource>) -> Self {
         ResourceMutex {
+            units: resources.len(),
             resources,
             queue: VecDeque::new(),
         }
     }
 
+    pub fn units(&self) -> usize {
+        self.units
+    }
+
+    /// Units nobody holds.
+    pub fn available(&self) -> usize {
+        self.resources.len()
+    }
+
+    /// Makes `process` wait even if a unit is free.
+    pub fn enqueue(&mut self, process: Process) {
+        self.queue.push_back(process);
+    }
+
+    /// Gives a free unit to the waiting process `pid`, if there is one.
+    pub fn grant(&mut self, pid: usize) -> Option<(Resource, Process)> {
+        if self.resources.is_empty() {
+            return None;
+        }
+        let process = self.remove(pid)?;
+        Some((self.resources.pop()?, process))
+    }
+
+    /// Takes back a unit without handing it to a waiting process.
+    pub fn put_back(&mut self, resource: Resource) {
+        self.resources.push(resource);
+    }
+
+    /// Processes waiting for a unit, in arrival order.
+    pub fn queue(&self) -> impl Iterator<Item = &Process> {
+        self.queue.iter()
+    }
+
     pub fn request(&mut self, process: Process) -> Option<(Resource, Process)> {
         let resource = self.resources.pop();
         match resource {
@@ -34,6 +106,20 @@ impl ResourceMutex {
         }
     }
 
+    /// Processes waiting for a unit, in arrival order.
+    pub fn queue_mut(&mut self) -> impl Iterator<Item = &mut Process> {
+        self.queue.iter_mut()
+    }
+
+    /// Takes the waiting process `pid` out of the queue.
+    pub fn remove(&mut self, pid: usize) -> Option<Process> {
+        let index = self
+            .queue
+            .iter()
+            .position(|process| process.software_context.id == pid)?;
+        self.queue.remove(index)
+    }
+
     pub fn release(&mut self, resource: Resource) -> Option<(Resource, Process)> {
         let process = self.queue.pop_front();
         match process {
@@ -48,6 +134,7 @@ impl ResourceMutex {
 
 #[cfg(test)]
 mod tests {
+    use crate::memory::AddressSpace;
     use crate::structures::segment_list::Segment;
 
     use super::*;
@@ -62,10 +149,12 @@ mod tests {
             false,
             false,
             vec![],
-            Segment {
+            vec![],
+            vec![],
+            AddressSpace::Contiguous(Segment {
                 offset: 0,
                 length: 0,
-            },
+            }),
         )
     }
 
@@ -0,0 +1,138 @@
+mod fcfs;
+mod feedback_queues;
+mod round_robin;
+mod shortest_job_first;
+mod shortest_remaining_time_first;
+
+use std::str::FromStr;
+
+use crate::events::SimEvent;
+use crate::process::Process;
+
+pub use self::fcfs::Fcfs;
+pub use self::feedback_queues::{AgingPolicy, FeedbackQueues};
+pub use self::round_robin::RoundRobin;
+pub use self::shortest_job_first::ShortestJobFirst;
+pub use self::shortest_remaining_time_first::ShortestRemainingTimeFirst;
+
+/// Why a user process is being handed back to the scheduler.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EnqueueReason {
+    Arrived,
+    Unblocked,
+    /// The scheduler asked for the process to leave the CPU.
+    Preempted,
+    /// A real-time process took the CPU.
+    Displaced,
+}
+
+/// Scheduling policy for user processes. Real-time processes are always
+/// served first, in arrival order, by the `ProcessManager`.
+pub trait Scheduler {
+    /// Adds a ready process and returns the queue it was placed in.
+    fn enqueue(&mut self, process: Process, reason: EnqueueReason) -> usize;
+
+    /// Removes the process that should run next.
+    fn pick_next(&mut self) -> Option<Process>;
+
+    /// Called once per tick, before deciding on preemption.
+    fn on_tick(&mut self, _timestamp: usize, _events: &mut Vec<SimEvent>) {}
+
+    /// Whether `running`, on the CPU for `elapsed` ticks, should give it up.
+    fn should_preempt(&self, running: &Process, elapsed: usize) -> bool;
+
+    fn has_processes(&self) -> bool;
+
+    /// Every ready process, in no particular order.
+    fn processes_mut(&mut self) -> Vec<&mut Process>;
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SchedulerKind {
+    FeedbackQueues,
+    Fcfs,
+    ShortestJobFirst,
+    ShortestRemainingTimeFirst,
+    RoundRobin,
+}
+
+impl SchedulerKind {
+    /// Builds the scheduler; `quanta` holds one quantum per feedback queue,
+    /// round robin uses the first one.
+    pub fn build(&self, quanta: &[usize], aging: AgingPolicy) -> Box<dyn Scheduler> {
+        match self {
+            SchedulerKind::FeedbackQueues => {
+                Box::new(FeedbackQueues::with_options(quanta.to_vec(), aging))
+            }
+            SchedulerKind::Fcfs => Box::new(Fcfs::new()),
+            SchedulerKind::ShortestJobFirst => Box::new(ShortestJobFirst::new()),
+            SchedulerKind::ShortestRemainingTimeFirst => {
+                Box::new(ShortestRemainingTimeFirst::new())
+            }
+            SchedulerKind::RoundRobin => Box::new(RoundRobin::new(quanta[0])),
+        }
+    }
+}
+
+impl FromStr for SchedulerKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "feedback" => Ok(SchedulerKind::FeedbackQueues),
+            "fcfs" => Ok(SchedulerKind::Fcfs),
+            "sjf" => Ok(SchedulerKind::ShortestJobFirst),
+            "srtf" => Ok(SchedulerKind::ShortestRemainingTimeFirst),
+            "rr" => Ok(SchedulerKind::RoundRobin),
+            _ => Err(format!(
+                "unknown scheduler `{}`, expected one of feedback, fcfs, sjf, srtf, rr",
+                name
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::memory::AddressSpace;
+    use crate::structures::segment_list::Segment;
+
+    use super::*;
+
+    pub fn create_process_mock(id: usize, priority: usize, cpu_time: usize) -> Process {
+        Process::new(
+            id,
+            priority,
+            cpu_time,
+            false,
+            false,
+            false,
+            false,
+            vec![],
+            vec![],
+            vec![],
+            AddressSpace::Contiguous(Segment {
+                offset: 0,
+                length: 0,
+            }),
+        )
+    }
+
+    pub fn pick_order(scheduler: &mut dyn Scheduler) -> Vec<usize> {
+        let mut order = Vec::new();
+        while let Some(process) = scheduler.pick_next() {
+            order.push(process.software_context.id);
+        }
+        order
+    }
+
+    #[test]
+    fn parses_scheduler_names() {
+        assert_eq!(
+            "srtf".parse(),
+            Ok(SchedulerKind::ShortestRemainingTimeFirst)
+        );
+        assert_eq!("feedback".parse(), Ok(SchedulerKind::FeedbackQueues));
+        assert!("lottery".parse::<SchedulerKind>().is_err());
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+use crate::process::Process;
+
+use super::{EnqueueReason, Scheduler};
+
+/// First come, first served: a single FIFO queue, never preempts.
+pub struct Fcfs {
+    queue: VecDeque<Process>,
+}
+
+impl Fcfs {
+    pub fn new() -> Self {
+        Fcfs {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Default for Fcfs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for Fcfs {
+    fn enqueue(&mut self, process: Process, reason: EnqueueReason) -> usize {
+        let priority = process.software_context.priority;
+        match reason {
+            EnqueueReason::Displaced => self.queue.push_front(process),
+            _ => self.queue.push_back(process),
+        }
+        priority
+    }
+
+    fn pick_next(&mut self) -> Option<Process> {
+        self.queue.pop_front()
+    }
+
+    fn should_preempt(&self, _running: &Process, _elapsed: usize) -> bool {
+        false
+    }
+
+    fn has_processes(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn processes_mut(&mut self) -> Vec<&mut Process> {
+        self.queue.iter_mut().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_process_mock, pick_order};
+    use super::*;
+
+    #[test]
+    fn serves_in_arrival_order() {
+        let mut scheduler = Fcfs::new();
+        scheduler.enqueue(create_process_mock(0, 3, 5), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(1, 1, 1), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(2, 2, 3), EnqueueReason::Displaced);
+        assert_eq!(pick_order(&mut scheduler), vec![2, 0, 1]);
+        assert!(!scheduler.should_preempt(&create_process_mock(3, 1, 9), 100));
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::VecDeque;
+
+use crate::events::SimEvent;
+use crate::process::Process;
+
+use super::{EnqueueReason, Scheduler};
+
+const USER_QUEUES: usize = 3;
+const USER_PROCESS_QUANTUM: usize = 1;
+
+/// Anti-starvation settings for `FeedbackQueues`.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct AgingPolicy {
+    /// Ticks a process may wait in a queue before it is promoted one level.
+    pub threshold: Option<usize>,
+    /// Every `boost_interval` ticks all waiting processes go back to the first queue.
+    pub boost_interval: Option<usize>,
+}
+
+struct QueueEntry {
+    process: Process,
+    waiting_since: usize,
+}
+
+/// Multilevel feedback queues for user priorities `1..=quanta.len()`: a
+/// process that uses up the quantum of its level is demoted one level, and
+/// optionally promoted again when it waits too long.
+pub struct FeedbackQueues {
+    queues: Vec<VecDeque<QueueEntry>>,
+    quanta: Vec<usize>,
+    aging: AgingPolicy,
+    timestamp: usize,
+}
+
+impl FeedbackQueues {
+    pub fn new() -> Self {
+        FeedbackQueues::with_options(
+            vec![USER_PROCESS_QUANTUM; USER_QUEUES],
+            AgingPolicy::default(),
+        )
+    }
+
+    /// One queue per entry of `quanta`, which holds the quantum of that level.
+    pub fn with_options(quanta: Vec<usize>, aging: AgingPolicy) -> Self {
+        FeedbackQueues {
+            queues: quanta.iter().map(|_| VecDeque::new()).collect(),
+            quanta,
+            aging,
+            timestamp: 0,
+        }
+    }
+
+    fn boost(&mut self, events: &mut Vec<SimEvent>) {
+        events.push(SimEvent::PriorityBoost);
+        let (first_queue, lower_queues) = self.queues.split_first_mut().unwrap();
+        for (level, queue) in lower_queues.iter_mut().enumerate() {
+            for mut entry in queue.drain(..) {
+                events.push(SimEvent::Promoted {
+                    pid: entry.process.software_context.id,
+                    from: level + 2,
+                    to: 1,
+                });
+                entry.process.software_context.priority = 1;
+                entry.waiting_since = self.timestamp;
+                first_queue.push_back(entry);
+            }
+        }
+    }
+
+    fn age(&mut self, threshold: usize, events: &mut Vec<SimEvent>) {
+        for index in 1..self.queues.len() {
+            let timestamp = self.timestamp;
+            let (waited, remaining): (VecDeque<QueueEntry>, VecDeque<QueueEntry>) = self.queues
+                [index]
+                .drain(..)
+                .partition(|entry| timestamp - entry.waiting_since >= threshold);
+            self.queues[index] = remaining;
+            for mut entry in waited {
+                events.push(SimEvent::Promoted {
+                    pid: entry.process.software_context.id,
+                    from: index + 1,
+                    to: index,
+                });
+                entry.process.software_context.priority = index;
+                entry.waiting_since = timestamp;
+                self.queues[index - 1].push_back(entry);
+            }
+        }
+    }
+}
+
+impl Default for FeedbackQueues {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for FeedbackQueues {
+    fn enqueue(&mut self, mut process: Process, reason: EnqueueReason) -> usize {
+        let priority = match reason {
+            EnqueueReason::Preempted => process.software_context.priority + 1,
+            _ => process.software_context.priority,
+        };
+        // Priorities past the last queue go to it.
+        let priority = priority.clamp(1, self.queues.len());
+        process.software_context.priority = priority;
+        let entry = QueueEntry {
+            process,
+            waiting_since: self.timestamp,
+        };
+        match reason {
+            EnqueueReason::Displaced => self.queues[priority - 1].push_front(entry),
+            _ => self.queues[priority - 1].push_back(entry),
+        }
+        priority
+    }
+
+    fn pick_next(&mut self) -> Option<Process> {
+        self.queues
+            .iter_mut()
+            .find_map(|queue| queue.pop_front())
+            .map(|entry| entry.process)
+    }
+
+    fn on_tick(&mut self, timestamp: usize, events: &mut Vec<SimEvent>) {
+        self.timestamp = timestamp;
+        match self.aging.boost_interval {
+            Some(interval) if interval > 0 && timestamp.is_multiple_of(interval) => {
+                self.boost(events)
+            }
+            _ => {}
+        }
+        if let Some(threshold) = self.aging.threshold {
+            self.age(threshold, events);
+        }
+    }
+
+    fn should_preempt(&self, running: &Process, elapsed: usize) -> bool {
+        let level = running
+            .software_context
+            .priority
+            .clamp(1, self.quanta.len());
+        elapsed >= self.quanta[level - 1]
+    }
+
+    fn has_processes(&self) -> bool {
+        self.queues.iter().any(|queue| !queue.is_empty())
+    }
+
+    fn processes_mut(&mut self) -> Vec<&mut Process> {
+        self.queues
+            .iter_mut()
+            .flatten()
+            .map(|entry| &mut entry.process)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_process_mock, pick_order};
+    use super::*;
+
+    #[test]
+    fn serves_higher_queues_first() {
+        let mut scheduler = FeedbackQueues::new();
+        scheduler.enqueue(create_process_mock(0, 3, 1), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(1, 2, 1), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(2, 1, 1), EnqueueReason::Arrived);
+        assert_eq!(pick_order(&mut scheduler), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn demotes_preempted_processes() {
+        let mut scheduler = FeedbackQueues::new();
+        let queue = scheduler.enqueue(create_process_mock(0, 1, 1), EnqueueReason::Preempted);
+        assert_eq!(queue, 2);
+        let queue = scheduler.enqueue(create_process_mock(1, 3, 1), EnqueueReason::Preempted);
+        assert_eq!(queue, 3);
+        let queue = scheduler.enqueue(create_process_mock(2, 1, 1), EnqueueReason::Unblocked);
+        assert_eq!(queue, 1);
+    }
+
+    #[test]
+    fn puts_priorities_past_the_last_queue_in_it() {
+        let mut scheduler = FeedbackQueues::new();
+        let queue = scheduler.enqueue(create_process_mock(0, 7, 1), EnqueueReason::Arrived);
+        assert_eq!(queue, 3);
+        let queue = scheduler.enqueue(create_process_mock(1, 5, 1), EnqueueReason::Displaced);
+        assert_eq!(queue, 3);
+        assert_eq!(pick_order(&mut scheduler), vec![1, 0]);
+    }
+
+    #[test]
+    fn promotes_processes_that_waited_too_long() {
+        let mut scheduler = FeedbackQueues::with_options(
+            vec![1; 3],
+            AgingPolicy {
+                threshold: Some(2),
+                boost_interval: None,
+            },
+        );
+        scheduler.enqueue(create_process_mock(0, 3, 1), EnqueueReason::Arrived);
+        let mut events = vec![];
+        scheduler.on_tick(1, &mut events);
+        assert!(events.is_empty());
+        scheduler.on_tick(2, &mut events);
+        assert_eq!(
+            events,
+            vec![SimEvent::Promoted {
+                pid: 0,
+                from: 3,
+                to: 2
+            }]
+        );
+        scheduler.enqueue(create_process_mock(1, 2, 1), EnqueueReason::Arrived);
+        scheduler.on_tick(4, &mut events);
+        let process = scheduler.pick_next().unwrap();
+        assert_eq!(process.software_context.id, 0);
+        assert_eq!(process.software_context.priority, 1);
+    }
+
+    #[test]
+    fn boosts_every_process_periodically() {
+        let mut scheduler = FeedbackQueues::with_options(
+            vec![1; 3],
+            AgingPolicy {
+                threshold: None,
+                boost_interval: Some(5),
+            },
+        );
+        scheduler.enqueue(create_process_mock(0, 3, 1), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(1, 2, 1), EnqueueReason::Arrived);
+        let mut events = vec![];
+        scheduler.on_tick(4, &mut events);
+        assert!(events.is_empty());
+        scheduler.on_tick(5, &mut events);
+        assert_eq!(events[0], SimEvent::PriorityBoost);
+        assert_eq!(events.len(), 3);
+        assert_eq!(pick_order(&mut scheduler), vec![1, 0]);
+    }
+
+    #[test]
+    fn uses_the_quantum_of_each_level() {
+        let scheduler = FeedbackQueues::with_options(vec![1, 2, 4], AgingPolicy::default());
+        let process = create_process_mock(0, 2, 10);
+        assert!(!scheduler.should_preempt(&process, 1));
+        assert!(scheduler.should_preempt(&process, 2));
+        let process = create_process_mock(0, 3, 10);
+        assert!(!scheduler.should_preempt(&process, 3));
+        assert!(scheduler.should_preempt(&process, 4));
+    }
+}
@@ -0,0 +1,74 @@
+use std::collections::VecDeque;
+
+use crate::process::Process;
+
+use super::{EnqueueReason, Scheduler};
+
+const DEFAULT_QUANTUM: usize = 1;
+
+/// A single FIFO queue where every process runs for at most `quantum` ticks.
+pub struct RoundRobin {
+    queue: VecDeque<Process>,
+    quantum: usize,
+}
+
+impl RoundRobin {
+    pub fn new(quantum: usize) -> Self {
+        RoundRobin {
+            queue: VecDeque::new(),
+            quantum,
+        }
+    }
+}
+
+impl Default for RoundRobin {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUANTUM)
+    }
+}
+
+impl Scheduler for RoundRobin {
+    fn enqueue(&mut self, process: Process, _reason: EnqueueReason) -> usize {
+        let priority = process.software_context.priority;
+        self.queue.push_back(process);
+        priority
+    }
+
+    fn pick_next(&mut self) -> Option<Process> {
+        self.queue.pop_front()
+    }
+
+    fn should_preempt(&self, _running: &Process, elapsed: usize) -> bool {
+        elapsed >= self.quantum
+    }
+
+    fn has_processes(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn processes_mut(&mut self) -> Vec<&mut Process> {
+        self.queue.iter_mut().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_process_mock, pick_order};
+    use super::*;
+
+    #[test]
+    fn ignores_priorities() {
+        let mut scheduler = RoundRobin::new(2);
+        scheduler.enqueue(create_process_mock(0, 3, 5), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(1, 1, 1), EnqueueReason::Arrived);
+        assert_eq!(pick_order(&mut scheduler), vec![0, 1]);
+    }
+
+    #[test]
+    fn preempts_after_quantum() {
+        let scheduler = RoundRobin::new(2);
+        let process = create_process_mock(0, 1, 5);
+        assert!(!scheduler.should_preempt(&process, 1));
+        assert!(scheduler.should_preempt(&process, 2));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::process::Process;
+
+use super::{EnqueueReason, Scheduler};
+
+/// Runs the ready process with the least remaining time to completion, without preemption.
+pub struct ShortestJobFirst {
+    queue: Vec<Process>,
+}
+
+impl ShortestJobFirst {
+    pub fn new() -> Self {
+        ShortestJobFirst { queue: Vec::new() }
+    }
+}
+
+impl Default for ShortestJobFirst {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Removes the shortest process of `queue`, the earliest one on ties.
+pub(super) fn take_shortest(queue: &mut Vec<Process>) -> Option<Process> {
+    let index = queue
+        .iter()
+        .enumerate()
+        .min_by_key(|(index, process)| (process.remaining_time(), *index))
+        .map(|(index, _)| index)?;
+    Some(queue.remove(index))
+}
+
+impl Scheduler for ShortestJobFirst {
+    fn enqueue(&mut self, process: Process, _reason: EnqueueReason) -> usize {
+        let priority = process.software_context.priority;
+        self.queue.push(process);
+        priority
+    }
+
+    fn pick_next(&mut self) -> Option<Process> {
+        take_shortest(&mut self.queue)
+    }
+
+    fn should_preempt(&self, _running: &Process, _elapsed: usize) -> bool {
+        false
+    }
+
+    fn has_processes(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn processes_mut(&mut self) -> Vec<&mut Process> {
+        self.queue.iter_mut().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{create_process_mock, pick_order};
+    use super::*;
+
+    #[test]
+    fn picks_shortest_first() {
+        let mut scheduler = ShortestJobFirst::new();
+        scheduler.enqueue(create_process_mock(0, 1, 5), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(1, 1, 1), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(2, 1, 3), EnqueueReason::Arrived);
+        scheduler.enqueue(create_process_mock(3, 1, 1), EnqueueReason::Arrived);
+        assert_eq!(pick_order(&mut scheduler), vec![1, 3, 2, 0]);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::process::Process;
+
+use super::shortest_job_first::take_shortest;
+use super::{EnqueueReason, Scheduler};
+
+/// Preemptive shortest job first: a ready process with less remaining time
+/// than the running one takes the CPU.
+pub struct ShortestRemainingTimeFirst {
+    queue: Vec<Process>,
+}
+
+impl ShortestRemainingTimeFirst {
+    pub fn new() -> Self {
+        ShortestRemainingTimeFirst { queue: Vec::new() }
+    }
+}
+
+impl Default for ShortestRemainingTimeFirst {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scheduler for ShortestRemainingTimeFirst {
+    fn enqueue(&mut self, process: Process, _reason: EnqueueReason) -> usize {
+        let priority = process.software_context.priority;
+        self.queue.push(process);
+        priority
+    }
+
+    fn pick_next(&mut self) -> Option<Process> {
+        take_shortest(&mut self.queue)
+    }
+
+    fn should_preempt(&self, running: &Process, _elapsed: usize) -> bool {
+        self.queue
+            .iter()
+            .any(|process| process.remaining_time() < running.remaining_time())
+    }
+
+    fn has_processes(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    fn processes_mut(&mut self) -> Vec<&mut Process> {
+        self.queue.iter_mut().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::create_process_mock;
+    use super::*;
+
+    #[test]
+    fn preempts_for_shorter_process() {
+        let mut scheduler = ShortestRemainingTimeFirst::new();
+        let running = create_process_mock(0, 1, 3);
+        assert!(!scheduler.should_preempt(&running, 1));
+        scheduler.enqueue(create_process_mock(1, 1, 3), EnqueueReason::Arrived);
+        assert!(!scheduler.should_preempt(&running, 1));
+        scheduler.enqueue(create_process_mock(2, 1, 2), EnqueueReason::Arrived);
+        assert!(scheduler.should_preempt(&running, 1));
+        assert_eq!(
+            scheduler
+                .pick_next()
+                .map(|process| process.software_context.id),
+            Some(2)
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::str::FromStr;
+
+use crate::structures::segment_list::Segment;
+
+/// The logical segments a process can be split into.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SegmentKind {
+    Code,
+    Data,
+    Stack,
+}
+
+impl SegmentKind {
+    pub const ALL: [SegmentKind; 3] = [SegmentKind::Code, SegmentKind::Data, SegmentKind::Stack];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SegmentKind::Code => "code",
+            SegmentKind::Data => "data",
+            SegmentKind::Stack => "stack",
+        }
+    }
+}
+
+impl FromStr for SegmentKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        SegmentKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "unknown segment `{}`, expected one of code, data, stack",
+                    name
+                )
+            })
+    }
+}
+
+/// Blocks declared for each segment of a process.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SegmentSizes {
+    pub code: usize,
+    pub data: usize,
+    pub stack: usize,
+}
+
+impl SegmentSizes {
+    pub fn size(&self, kind: SegmentKind) -> usize {
+        match kind {
+            SegmentKind::Code => self.code,
+            SegmentKind::Data => self.data,
+            SegmentKind::Stack => self.stack,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.code + self.data + self.stack
+    }
+}
+
+/// An access past the end of a segment.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct ProtectionFault {
+    pub segment: SegmentKind,
+    pub offset: usize,
+    /// Length of the segment, the first offset out of bounds.
+    pub limit: usize,
+}
+
+/// Base and limit of every segment of a process. Empty segments have no entry.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SegmentTable {
+    entries: Vec<(SegmentKind, Segment)>,
+}
+
+impl SegmentTable {
+    pub fn new(entries: Vec<(SegmentKind, Segment)>) -> Self {
+        SegmentTable { entries }
+    }
+
+    pub fn entries(&self) -> &[(SegmentKind, Segment)] {
+        &self.entries
+    }
+
+    pub fn segment(&self, kind: SegmentKind) -> Option<&Segment> {
+        self.entries
+            .iter()
+            .find(|(entry_kind, _)| *entry_kind == kind)
+            .map(|(_, segment)| segment)
+    }
+
+    pub fn segment_mut(&mut self, kind: SegmentKind) -> Option<&mut Segment> {
+        self.entries
+            .iter_mut()
+            .find(|(entry_kind, _)| *entry_kind == kind)
+            .map(|(_, segment)| segment)
+    }
+
+    pub fn segments_mut(&mut self) -> impl Iterator<Item = &mut Segment> {
+        self.entries.iter_mut().map(|(_, segment)| segment)
+    }
+
+    pub fn into_segments(self) -> impl Iterator<Item = Segment> {
+        self.entries.into_iter().map(|(_, segment)| segment)
+    }
+
+    pub fn sizes(&self) -> SegmentSizes {
+        let size = |kind| {
+            self.entries
+                .iter()
+                .find(|(entry_kind, _)| *entry_kind == kind)
+                .map_or(0, |(_, segment)| segment.length)
+        };
+        SegmentSizes {
+            code: size(SegmentKind::Code),
+            data: size(SegmentKind::Data),
+            stack: size(SegmentKind::Stack),
+        }
+    }
+
+    /// Physical block of `offset` within `segment`.
+    pub fn translate(&self, segment: SegmentKind, offset: usize) -> Result<usize, ProtectionFault> {
+        match self.entries.iter().find(|(kind, _)| *kind == segment) {
+            Some((_, base)) if offset < base.length => Ok(base.offset + offset),
+            entry => Err(ProtectionFault {
+                segment,
+                offset,
+                limit: entry.map_or(0, |(_, base)| base.length),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_segment_table() -> SegmentTable {
+        SegmentTable::new(vec![
+            (
+                SegmentKind::Code,
+                Segment {
+                    offset: 100,
+                    length: 8,
+                },
+            ),
+            (
+                SegmentKind::Data,
+                Segment {
+                    offset: 64,
+                    length: 4,
+                },
+            ),
+        ])
+    }
+
+    mod translate {
+        use super::*;
+
+        #[test]
+        fn adds_the_segment_base() {
+            let segment_table = create_segment_table();
+            assert_eq!(segment_table.translate(SegmentKind::Code, 7), Ok(107));
+            assert_eq!(segment_table.translate(SegmentKind::Data, 0), Ok(64));
+        }
+
+        #[test]
+        fn faults_past_the_limit() {
+            let segment_table = create_segment_table();
+            assert_eq!(
+                segment_table.translate(SegmentKind::Data, 4),
+                Err(ProtectionFault {
+                    segment: SegmentKind::Data,
+                    offset: 4,
+                    limit: 4
+                })
+            );
+            assert_eq!(
+                segment_table.translate(SegmentKind::Stack, 0),
+                Err(ProtectionFault {
+                    segment: SegmentKind::Stack,
+                    offset: 0,
+                    limit: 0
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn sizes_of_missing_segments_are_zero() {
+        assert_eq!(
+            create_segment_table().sizes(),
+            SegmentSizes {
+                code: 8,
+                data: 4,
+                stack: 0
+            }
+        );
+    }
+}
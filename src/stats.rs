@@ -0,0 +1,925 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::events::{SimEvent, StorageArea};
+use crate::resources::Resource;
+use crate::segmentation::ProtectionFault;
+use crate::structures::segment_list::FreeSpace;
+
+/// Scheduling figures of a single process, derived from the events of a run.
+#[derive(Debug, Default, PartialEq)]
+pub struct ProcessStats {
+    pub priority: usize,
+    pub arrival_time: usize,
+    pub creation_time: usize,
+    pub first_dispatch: Option<usize>,
+    pub completion_time: Option<usize>,
+    pub cpu_ticks: usize,
+    /// Ticks spent ready in each queue, indexed by queue.
+    pub queue_ticks: BTreeMap<usize, usize>,
+    /// Ticks spent blocked on each resource.
+    pub blocked_ticks: Vec<(Resource, usize)>,
+    /// Times the process was promoted by aging or a priority boost.
+    pub promotions: usize,
+    /// Longest uninterrupted stretch spent ready without running.
+    pub longest_wait: usize,
+    /// Accesses to pages of a paged address space.
+    pub page_references: usize,
+    pub page_faults: usize,
+    /// Accesses to segments, the faulting one included.
+    pub segment_accesses: usize,
+    /// The access that terminated the process, if any.
+    pub protection_fault: Option<ProtectionFault>,
+    /// Times the process was swapped out while blocked.
+    pub swaps: usize,
+    /// Ticks between being swapped out and being ready again.
+    pub swapped_ticks: usize,
+    /// Whether the process was terminated to break a deadlock.
+    pub aborted: bool,
+    /// Times the process was rolled back to break a deadlock.
+    pub rollbacks: usize,
+    enqueued_at: Option<(usize, usize)>,
+    ready_since: Option<usize>,
+    blocked_at: Option<(Resource, usize)>,
+    swapped_at: Option<usize>,
+}
+
+impl ProcessStats {
+    /// Ticks between arrival and creation, waiting for memory.
+    pub fn memory_wait(&self) -> usize {
+        self.creation_time - self.arrival_time
+    }
+
+    pub fn turnaround(&self) -> Option<usize> {
+        Some(self.completion_time? - self.arrival_time)
+    }
+
+    /// Ticks between arrival and completion not spent on the CPU.
+    pub fn waiting(&self) -> Option<usize> {
+        Some(self.turnaround()? - self.cpu_ticks)
+    }
+
+    pub fn response(&self) -> Option<usize> {
+        Some(self.first_dispatch? - self.arrival_time)
+    }
+
+    /// Fraction of page references that faulted.
+    pub fn fault_rate(&self) -> f64 {
+        ratio(self.page_faults, self.page_references)
+    }
+
+    fn add_blocked_ticks(&mut self, resource: Resource, ticks: usize) {
+        match self
+            .blocked_ticks
+            .iter_mut()
+            .find(|(blocked_resource, _)| *blocked_resource == resource)
+        {
+            Some((_, total)) => *total += ticks,
+            None => self.blocked_ticks.push((resource, ticks)),
+        }
+    }
+}
+
+/// Free space figures of a memory partition or of the disk over a run.
+#[derive(Debug, Default, PartialEq)]
+pub struct FreeSpaceStats {
+    pub samples: usize,
+    pub peak_occupancy: f64,
+    pub peak_fragmentation: f64,
+    pub max_holes: usize,
+    /// Smallest largest hole seen, the tightest the area ever got.
+    pub min_largest_hole: usize,
+    pub peak_internal_fragmentation: usize,
+    occupancy_sum: f64,
+    fragmentation_sum: f64,
+}
+
+impl FreeSpaceStats {
+    pub fn average_occupancy(&self) -> f64 {
+        average(self.occupancy_sum, self.samples)
+    }
+
+    pub fn average_fragmentation(&self) -> f64 {
+        average(self.fragmentation_sum, self.samples)
+    }
+
+    fn add_sample(&mut self, space: &FreeSpace) {
+        if self.samples == 0 {
+            self.min_largest_hole = space.largest_hole;
+        }
+        self.samples += 1;
+        self.occupancy_sum += space.occupancy();
+        self.fragmentation_sum += space.fragmentation();
+        self.peak_occupancy = self.peak_occupancy.max(space.occupancy());
+        self.peak_fragmentation = self.peak_fragmentation.max(space.fragmentation());
+        self.max_holes = self.max_holes.max(space.holes);
+        self.min_largest_hole = self.min_largest_hole.min(space.largest_hole);
+        self.peak_internal_fragmentation = self
+            .peak_internal_fragmentation
+            .max(space.internal_fragmentation);
+    }
+}
+
+/// Totals of the memory compactions of a run.
+#[derive(Debug, Default, PartialEq)]
+pub struct CompactionStats {
+    pub count: usize,
+    pub moved_blocks: usize,
+    pub relocated: usize,
+    /// Ticks the compactions would have taken.
+    pub cost: usize,
+}
+
+/// Aggregates per-process and CPU figures from the event stream.
+#[derive(Debug, Default)]
+pub struct Statistics {
+    processes: BTreeMap<usize, ProcessStats>,
+    busy_ticks: usize,
+    idle_ticks: usize,
+    free_space: BTreeMap<StorageArea, FreeSpaceStats>,
+    compactions: CompactionStats,
+    /// Tick and pids of every deadlock detected.
+    deadlocks: Vec<(usize, Vec<usize>)>,
+    /// Tick the run stopped at and the processes it left behind.
+    stranded: Option<(usize, Vec<usize>)>,
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn process(&self, pid: usize) -> Option<&ProcessStats> {
+        self.processes.get(&pid)
+    }
+
+    pub fn free_space(&self, area: StorageArea) -> Option<&FreeSpaceStats> {
+        self.free_space.get(&area)
+    }
+
+    pub fn compactions(&self) -> &CompactionStats {
+        &self.compactions
+    }
+
+    pub fn deadlocks(&self) -> &[(usize, Vec<usize>)] {
+        &self.deadlocks
+    }
+
+    pub fn stranded(&self) -> Option<&(usize, Vec<usize>)> {
+        self.stranded.as_ref()
+    }
+
+    pub fn total_ticks(&self) -> usize {
+        self.busy_ticks + self.idle_ticks
+    }
+
+    pub fn cpu_utilization(&self) -> f64 {
+        ratio(self.busy_ticks, self.total_ticks())
+    }
+
+    /// Completed processes per tick.
+    pub fn throughput(&self) -> f64 {
+        let completed = self
+            .processes
+            .values()
+            .filter(|process| process.completion_time.is_some())
+            .count();
+        ratio(completed, self.total_ticks())
+    }
+
+    pub fn record(&mut self, timestamp: usize, event: &SimEvent) {
+        match event {
+            SimEvent::ProcessCreated {
+                pid,
+                priority,
+                arrival_time,
+                ..
+            } => {
+                self.processes.insert(
+                    *pid,
+                    ProcessStats {
+                        priority: *priority,
+                        arrival_time: *arrival_time,
+                        creation_time: timestamp,
+                        ..ProcessStats::default()
+                    },
+                );
+            }
+            SimEvent::Enqueued { pid, priority } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.enqueued_at = Some((*priority, timestamp));
+                    process.ready_since = Some(timestamp);
+                }
+            }
+            SimEvent::Dispatched { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.first_dispatch.get_or_insert(timestamp);
+                    if let Some((queue, since)) = process.enqueued_at.take() {
+                        *process.queue_ticks.entry(queue).or_default() += timestamp - since;
+                    }
+                    if let Some(since) = process.ready_since.take() {
+                        process.longest_wait = process.longest_wait.max(timestamp - since);
+                    }
+                }
+            }
+            SimEvent::Promoted { pid, to, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.promotions += 1;
+                    if let Some((queue, since)) = process.enqueued_at.replace((*to, timestamp)) {
+                        *process.queue_ticks.entry(queue).or_default() += timestamp - since;
+                    }
+                }
+            }
+            SimEvent::Executed { pid, .. } => {
+                self.busy_ticks += 1;
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.cpu_ticks += 1;
+                }
+            }
+            SimEvent::Idle => self.idle_ticks += 1,
+            SimEvent::Blocked { pid, resource, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.blocked_at = Some((*resource, timestamp));
+                }
+            }
+            SimEvent::Unblocked { pid, .. }
+            | SimEvent::Aborted { pid, .. }
+            | SimEvent::RolledBack { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    if let Some((resource, since)) = process.blocked_at.take() {
+                        process.add_blocked_ticks(resource, timestamp - since);
+                    }
+                    match event {
+                        SimEvent::Aborted { .. } => process.aborted = true,
+                        SimEvent::RolledBack { .. } => process.rollbacks += 1,
+                        _ => {}
+                    }
+                }
+            }
+            SimEvent::Terminated { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.completion_time = Some(timestamp + 1);
+                }
+            }
+            SimEvent::PageHit { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.page_references += 1;
+                }
+            }
+            SimEvent::PageFault { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.page_references += 1;
+                    process.page_faults += 1;
+                }
+            }
+            SimEvent::SegmentAccessed { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.segment_accesses += 1;
+                }
+            }
+            SimEvent::ProtectionFault {
+                pid,
+                segment,
+                offset,
+                limit,
+                ..
+            } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.segment_accesses += 1;
+                    process.protection_fault = Some(ProtectionFault {
+                        segment: *segment,
+                        offset: *offset,
+                        limit: *limit,
+                    });
+                }
+            }
+            SimEvent::SwappedOut { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    process.swaps += 1;
+                    process.swapped_at = Some(timestamp);
+                }
+            }
+            SimEvent::SwappedIn { pid, .. } => {
+                if let Some(process) = self.processes.get_mut(pid) {
+                    if let Some(since) = process.swapped_at.take() {
+                        process.swapped_ticks += timestamp - since;
+                    }
+                }
+            }
+            SimEvent::Compacted {
+                moved_blocks,
+                relocated,
+                cost,
+                ..
+            } => {
+                self.compactions.count += 1;
+                self.compactions.moved_blocks += moved_blocks;
+                self.compactions.relocated += relocated;
+                self.compactions.cost += cost;
+            }
+            SimEvent::Deadlocked { pids, .. } => self.deadlocks.push((timestamp, pids.clone())),
+            SimEvent::Stranded { pids } => self.stranded = Some((timestamp, pids.clone())),
+            SimEvent::FreeSpaceSampled { area, space } => {
+                self.free_space.entry(*area).or_default().add_sample(space)
+            }
+            _ => {}
+        }
+    }
+
+    /// Renders the per-process table followed by the run-wide figures.
+    pub fn render(&self) -> String {
+        let mut report = String::new();
+        writeln!(
+            report,
+            "{:>4} {:>4} {:>7} {:>7} {:>5} {:>4} {:>5} {:>10} {:>7} {:>8} {:>6} {:>8} {:>10}  {:<16} blocked",
+            "pid",
+            "prio",
+            "arrival",
+            "created",
+            "start",
+            "end",
+            "cpu",
+            "turnaround",
+            "waiting",
+            "response",
+            "memory",
+            "promoted",
+            "max wait",
+            "ready queues",
+        )
+        .unwrap();
+        for (pid, process) in self.processes.iter() {
+            let queue_ticks = process
+                .queue_ticks
+                .iter()
+                .map(|(queue, ticks)| format!("q{}:{}", queue, ticks))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let blocked_ticks = process
+                .blocked_ticks
+                .iter()
+                .map(|(resource, ticks)| format!("{}:{}", resource.name(), ticks))
+                .collect::<Vec<_>>()
+                .join(" ");
+            writeln!(
+                report,
+                "{:>4} {:>4} {:>7} {:>7} {:>5} {:>4} {:>5} {:>10} {:>7} {:>8} {:>6} {:>8} {:>10}  {:<16} {}",
+                pid,
+                process.priority,
+                process.arrival_time,
+                process.creation_time,
+                optional(process.first_dispatch),
+                optional(process.completion_time),
+                process.cpu_ticks,
+                optional(process.turnaround()),
+                optional(process.waiting()),
+                optional(process.response()),
+                process.memory_wait(),
+                process.promotions,
+                process.longest_wait,
+                queue_ticks,
+                blocked_ticks,
+            )
+            .unwrap();
+        }
+        writeln!(
+            report,
+            "\nAverage turnaround: {:.2}",
+            self.average(ProcessStats::turnaround)
+        )
+        .unwrap();
+        writeln!(
+            report,
+            "Average waiting: {:.2}",
+            self.average(ProcessStats::waiting)
+        )
+        .unwrap();
+        writeln!(
+            report,
+            "Average response: {:.2}",
+            self.average(ProcessStats::response)
+        )
+        .unwrap();
+        writeln!(
+            report,
+            "CPU utilization: {:.2}% ({} of {} ticks)",
+            self.cpu_utilization() * 100.0,
+            self.busy_ticks,
+            self.total_ticks()
+        )
+        .unwrap();
+        writeln!(
+            report,
+            "Throughput: {:.4} processes/tick",
+            self.throughput()
+        )
+        .unwrap();
+        self.render_paging(&mut report);
+        self.render_segmentation(&mut report);
+        self.render_swapping(&mut report);
+        if !self.free_space.is_empty() {
+            writeln!(
+                report,
+                "\n{:<16} {:>8} {:>9} {:>9} {:>10} {:>9} {:>12} {:>13}",
+                "free space",
+                "avg used",
+                "peak used",
+                "avg frag",
+                "peak frag",
+                "max holes",
+                "min largest",
+                "max internal"
+            )
+            .unwrap();
+        }
+        for (area, space) in self.free_space.iter() {
+            writeln!(
+                report,
+                "{:<16} {:>7.2}% {:>8.2}% {:>9.4} {:>10.4} {:>9} {:>12} {:>13}",
+                area.name(),
+                space.average_occupancy() * 100.0,
+                space.peak_occupancy * 100.0,
+                space.average_fragmentation(),
+                space.peak_fragmentation,
+                space.max_holes,
+                space.min_largest_hole,
+                space.peak_internal_fragmentation,
+            )
+            .unwrap();
+        }
+        if self.compactions.count > 0 {
+            writeln!(
+                report,
+                "\nCompactions: {} ({} blocks of {} processes moved, {} ticks)",
+                self.compactions.count,
+                self.compactions.moved_blocks,
+                self.compactions.relocated,
+                self.compactions.cost
+            )
+            .unwrap();
+        }
+        for (timestamp, pids) in &self.deadlocks {
+            writeln!(
+                report,
+                "\nDeadlock at tick {}: processes {:?}",
+                timestamp, pids
+            )
+            .unwrap();
+        }
+        if let Some((timestamp, pids)) = &self.stranded {
+            writeln!(
+                report,
+                "Stopped at tick {}: processes {:?} can never run",
+                timestamp, pids
+            )
+            .unwrap();
+        }
+        for (pid, process) in &self.processes {
+            if process.aborted {
+                writeln!(report, "Process {} was aborted", pid).unwrap();
+            }
+            if process.rollbacks > 0 {
+                writeln!(
+                    report,
+                    "Process {} was rolled back {} times",
+                    pid, process.rollbacks
+                )
+                .unwrap();
+            }
+        }
+        report
+    }
+
+    /// Page faults of every process that touched pages, and their total.
+    fn render_paging(&self, report: &mut String) {
+        let paged = self
+            .processes
+            .iter()
+            .filter(|(_, process)| process.page_references > 0)
+            .collect::<Vec<_>>();
+        if paged.is_empty() {
+            return;
+        }
+        writeln!(
+            report,
+            "\n{:>4} {:>10} {:>6} {:>10}",
+            "pid", "references", "faults", "fault rate"
+        )
+        .unwrap();
+        for (pid, process) in paged.iter() {
+            writeln!(
+                report,
+                "{:>4} {:>10} {:>6} {:>9.2}%",
+                pid,
+                process.page_references,
+                process.page_faults,
+                process.fault_rate() * 100.0
+            )
+            .unwrap();
+        }
+        let references = paged
+            .iter()
+            .map(|(_, process)| process.page_references)
+            .sum::<usize>();
+        let faults = paged
+            .iter()
+            .map(|(_, process)| process.page_faults)
+            .sum::<usize>();
+        writeln!(
+            report,
+            "Page faults: {} of {} references ({:.2}%)",
+            faults,
+            references,
+            ratio(faults, references) * 100.0
+        )
+        .unwrap();
+    }
+
+    /// Segment accesses and the protection faults that terminated processes.
+    fn render_segmentation(&self, report: &mut String) {
+        let accesses = self
+            .processes
+            .values()
+            .map(|process| process.segment_accesses)
+            .sum::<usize>();
+        if accesses == 0 {
+            return;
+        }
+        let faults = self
+            .processes
+            .iter()
+            .filter_map(|(pid, process)| Some((pid, process.protection_fault?)))
+            .collect::<Vec<_>>();
+        writeln!(
+            report,
+            "\nProtection faults: {} of {} segment accesses",
+            faults.len(),
+            accesses
+        )
+        .unwrap();
+        for (pid, fault) in faults {
+            writeln!(
+                report,
+                "  process {}: {} offset {} out of {} blocks",
+                pid,
+                fault.segment.name(),
+                fault.offset,
+                fault.limit
+            )
+            .unwrap();
+        }
+    }
+
+    /// Swaps of every process that was swapped out, and their total.
+    fn render_swapping(&self, report: &mut String) {
+        let swapped = self
+            .processes
+            .iter()
+            .filter(|(_, process)| process.swaps > 0)
+            .collect::<Vec<_>>();
+        if swapped.is_empty() {
+            return;
+        }
+        writeln!(
+            report,
+            "\n{:>4} {:>5} {:>13}",
+            "pid", "swaps", "swapped ticks"
+        )
+        .unwrap();
+        for (pid, process) in swapped.iter() {
+            writeln!(
+                report,
+                "{:>4} {:>5} {:>13}",
+                pid, process.swaps, process.swapped_ticks
+            )
+            .unwrap();
+        }
+        writeln!(
+            report,
+            "Swaps: {} ({} ticks swapped out)",
+            swapped
+                .iter()
+                .map(|(_, process)| process.swaps)
+                .sum::<usize>(),
+            swapped
+                .iter()
+                .map(|(_, process)| process.swapped_ticks)
+                .sum::<usize>()
+        )
+        .unwrap();
+    }
+
+    fn average(&self, metric: fn(&ProcessStats) -> Option<usize>) -> f64 {
+        let values = self
+            .processes
+            .values()
+            .filter_map(metric)
+            .collect::<Vec<_>>();
+        ratio(values.iter().sum(), values.len())
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn average(sum: f64, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f64
+    }
+}
+
+fn optional(value: Option<usize>) -> String {
+    value.map_or(String::from("-"), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::AddressSpace;
+    use crate::structures::segment_list::Segment;
+
+    use super::*;
+
+    fn created(pid: usize, priority: usize, arrival_time: usize) -> SimEvent {
+        SimEvent::ProcessCreated {
+            pid,
+            priority,
+            arrival_time,
+            cpu_time: 0,
+            address_space: AddressSpace::Contiguous(Segment {
+                offset: 0,
+                length: 1,
+            }),
+            resources: vec![],
+        }
+    }
+
+    fn terminated(pid: usize) -> SimEvent {
+        SimEvent::Terminated {
+            pid,
+            priority: 1,
+            address_space: AddressSpace::Contiguous(Segment {
+                offset: 0,
+                length: 1,
+            }),
+        }
+    }
+
+    #[test]
+    fn computes_process_times() {
+        let mut statistics = Statistics::new();
+        let events = [
+            (0, SimEvent::Idle),
+            (1, created(0, 1, 0)),
+            (
+                1,
+                SimEvent::Enqueued {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (
+                3,
+                SimEvent::Dispatched {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (
+                3,
+                SimEvent::Executed {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (
+                3,
+                SimEvent::Blocked {
+                    pid: 0,
+                    priority: 1,
+                    resource: Resource::Printer,
+                },
+            ),
+            (
+                5,
+                SimEvent::Unblocked {
+                    pid: 0,
+                    priority: 1,
+                    resource: Resource::Printer,
+                },
+            ),
+            (
+                5,
+                SimEvent::Enqueued {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (
+                5,
+                SimEvent::Dispatched {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (
+                5,
+                SimEvent::Executed {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (5, terminated(0)),
+        ];
+        for (timestamp, event) in events.iter() {
+            statistics.record(*timestamp, event);
+        }
+        let process = statistics.process(0).unwrap();
+        assert_eq!(process.memory_wait(), 1);
+        assert_eq!(process.response(), Some(3));
+        assert_eq!(process.turnaround(), Some(6));
+        assert_eq!(process.waiting(), Some(4));
+        assert_eq!(process.queue_ticks.get(&1), Some(&2));
+        assert_eq!(process.blocked_ticks, vec![(Resource::Printer, 2)]);
+        assert_eq!(statistics.total_ticks(), 3);
+    }
+
+    #[test]
+    fn computes_cpu_figures() {
+        let mut statistics = Statistics::new();
+        let events = [
+            (0, created(0, 0, 0)),
+            (
+                0,
+                SimEvent::Executed {
+                    pid: 0,
+                    priority: 0,
+                },
+            ),
+            (0, terminated(0)),
+            (1, SimEvent::Idle),
+            (2, created(1, 0, 2)),
+            (
+                2,
+                SimEvent::Executed {
+                    pid: 1,
+                    priority: 0,
+                },
+            ),
+            (3, SimEvent::Idle),
+        ];
+        for (timestamp, event) in events.iter() {
+            statistics.record(*timestamp, event);
+        }
+        assert_eq!(statistics.cpu_utilization(), 0.5);
+        assert_eq!(statistics.throughput(), 0.25);
+        assert!(statistics.render().contains("CPU utilization: 50.00%"));
+    }
+
+    #[test]
+    fn leaves_aborted_processes_out_of_completions() {
+        let mut statistics = Statistics::new();
+        let events = [
+            (0, created(0, 1, 0)),
+            (0, created(1, 1, 0)),
+            (
+                0,
+                SimEvent::Executed {
+                    pid: 0,
+                    priority: 1,
+                },
+            ),
+            (0, terminated(0)),
+            (
+                1,
+                SimEvent::Executed {
+                    pid: 1,
+                    priority: 1,
+                },
+            ),
+            (
+                1,
+                SimEvent::Aborted {
+                    pid: 1,
+                    priority: 1,
+                    address_space: AddressSpace::Contiguous(Segment {
+                        offset: 0,
+                        length: 1,
+                    }),
+                },
+            ),
+        ];
+        for (timestamp, event) in events.iter() {
+            statistics.record(*timestamp, event);
+        }
+        let aborted = statistics.process(1).unwrap();
+        assert!(aborted.aborted);
+        assert_eq!(aborted.turnaround(), None);
+        assert_eq!(statistics.throughput(), 0.5);
+    }
+
+    #[test]
+    fn counts_promotions_and_longest_wait() {
+        let mut statistics = Statistics::new();
+        let events = [
+            (0, created(0, 3, 0)),
+            (
+                0,
+                SimEvent::Enqueued {
+                    pid: 0,
+                    priority: 3,
+                },
+            ),
+            (
+                4,
+                SimEvent::Promoted {
+                    pid: 0,
+                    from: 3,
+                    to: 2,
+                },
+            ),
+            (
+                6,
+                SimEvent::Dispatched {
+                    pid: 0,
+                    priority: 2,
+                },
+            ),
+            (
+                7,
+                SimEvent::Enqueued {
+                    pid: 0,
+                    priority: 3,
+                },
+            ),
+            (
+                8,
+                SimEvent::Dispatched {
+                    pid: 0,
+                    priority: 3,
+                },
+            ),
+        ];
+        for (timestamp, event) in events.iter() {
+            statistics.record(*timestamp, event);
+        }
+        let process = statistics.process(0).unwrap();
+        assert_eq!(process.promotions, 1);
+        assert_eq!(process.longest_wait, 6);
+        assert_eq!(process.queue_ticks.get(&3), Some(&5));
+        assert_eq!(process.queue_ticks.get(&2), Some(&2));
+    }
+
+    #[test]
+    fn summarizes_free_space() {
+        let mut statistics = Statistics::new();
+        let sample = |free_blocks, holes, largest_hole| SimEvent::FreeSpaceSampled {
+            area: StorageArea::Disk,
+            space: FreeSpace {
+                capacity: 10,
+                free_blocks,
+                holes,
+                largest_hole,
+                internal_fragmentation: 0,
+            },
+        };
+        statistics.record(0, &sample(10, 1, 10));
+        statistics.record(1, &sample(4, 2, 2));
+        let disk = statistics.free_space(StorageArea::Disk).unwrap();
+        assert_eq!(disk.samples, 2);
+        assert_eq!(disk.max_holes, 2);
+        assert_eq!(disk.min_largest_hole, 2);
+        assert!((disk.average_occupancy() - 0.3).abs() < 1e-9);
+        assert!((disk.peak_fragmentation - 0.5).abs() < 1e-9);
+        assert!((disk.average_fragmentation() - 0.25).abs() < 1e-9);
+        assert_eq!(statistics.free_space(StorageArea::UserMemory), None);
+    }
+
+    #[test]
+    fn counts_page_faults() {
+        let mut statistics = Statistics::new();
+        statistics.record(0, &created(0, 1, 0));
+        statistics.record(
+            1,
+            &SimEvent::PageFault {
+                pid: 0,
+                page: 2,
+                frame: 64,
+                evicted: None,
+            },
+        );
+        statistics.record(2, &SimEvent::PageHit { pid: 0, page: 2 });
+        let process = statistics.process(0).unwrap();
+        assert_eq!((process.page_references, process.page_faults), (2, 1));
+        assert_eq!(process.fault_rate(), 0.5);
+        assert!(statistics
+            .render()
+            .contains("Page faults: 1 of 2 references (50.00%)"));
+    }
+}
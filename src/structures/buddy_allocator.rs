@@ -0,0 +1,184 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::segment_list::{FreeSpace, Segment};
+
+/// Binary buddy allocator over `[base, base + capacity)`.
+///
+/// A capacity that is not a power of two is split into power-of-two roots,
+/// largest first, so every root is aligned to its size and the buddy of a
+/// root is never a free block of the same order.
+#[derive(Debug, PartialEq)]
+pub struct BuddyAllocator {
+    base: usize,
+    capacity: usize,
+    /// Free block offsets, relative to `base`, indexed by order.
+    free_lists: Vec<BTreeSet<usize>>,
+    /// Order and requested length of every allocated block, by relative offset.
+    allocated: BTreeMap<usize, (u32, usize)>,
+}
+
+impl BuddyAllocator {
+    pub fn new(base: usize, capacity: usize) -> Self {
+        let max_order = if capacity == 0 { 0 } else { capacity.ilog2() };
+        let mut free_lists = vec![BTreeSet::new(); max_order as usize + 1];
+        let mut offset = 0;
+        while offset < capacity {
+            let order = (capacity - offset).ilog2();
+            free_lists[order as usize].insert(offset);
+            offset += 1 << order;
+        }
+        BuddyAllocator {
+            base,
+            capacity,
+            free_lists,
+            allocated: BTreeMap::new(),
+        }
+    }
+
+    /// Largest request that could ever be satisfied.
+    pub fn max_request(&self) -> usize {
+        match self.capacity {
+            0 => 0,
+            _ => 1 << (self.free_lists.len() - 1),
+        }
+    }
+
+    /// Allocates the smallest power-of-two block holding `length`, at the
+    /// lowest offset. The returned segment has the requested length.
+    pub fn alloc(&mut self, length: usize) -> Option<Segment> {
+        let order = length.max(1).next_power_of_two().ilog2();
+        let (mut block_order, offset) =
+            (order..self.free_lists.len() as u32).find_map(|candidate| {
+                let offset = *self.free_lists[candidate as usize].first()?;
+                Some((candidate, offset))
+            })?;
+        self.free_lists[block_order as usize].remove(&offset);
+        while block_order > order {
+            block_order -= 1;
+            self.free_lists[block_order as usize].insert(offset + (1 << block_order));
+        }
+        self.allocated.insert(offset, (order, length));
+        Some(Segment {
+            offset: self.base + offset,
+            length,
+        })
+    }
+
+    /// Frees a segment returned by `alloc`, coalescing it with its free buddies.
+    pub fn free(&mut self, segment: Segment) {
+        let mut offset = segment.offset - self.base;
+        let Some((mut order, _)) = self.allocated.remove(&offset) else {
+            return;
+        };
+        while (order as usize) + 1 < self.free_lists.len() {
+            let buddy = offset ^ (1 << order);
+            if !self.free_lists[order as usize].remove(&buddy) {
+                break;
+            }
+            offset = offset.min(buddy);
+            order += 1;
+        }
+        self.free_lists[order as usize].insert(offset);
+    }
+
+    /// Gives the allocated `segment` a new `length` within the block it
+    /// already holds, which is not split nor merged.
+    pub fn resize(&mut self, segment: &Segment, length: usize) -> bool {
+        match self.allocated.get_mut(&(segment.offset - self.base)) {
+            Some((order, requested)) if length <= 1 << *order => {
+                *requested = length;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Blocks handed out beyond what was requested.
+    pub fn internal_fragmentation(&self) -> usize {
+        self.allocated
+            .values()
+            .map(|(order, length)| (1 << order) - length)
+            .sum()
+    }
+
+    pub fn free_space(&self) -> FreeSpace {
+        let blocks = self
+            .free_lists
+            .iter()
+            .enumerate()
+            .flat_map(|(order, offsets)| offsets.iter().map(move |_| 1 << order));
+        FreeSpace {
+            capacity: self.capacity,
+            free_blocks: blocks.clone().sum(),
+            holes: blocks.clone().count(),
+            largest_hole: blocks.max().unwrap_or(0),
+            internal_fragmentation: self.internal_fragmentation(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod alloc {
+        use super::*;
+
+        #[test]
+        fn splits_down_to_the_smallest_fitting_block() {
+            let mut buddy = BuddyAllocator::new(64, 16);
+            assert_eq!(
+                buddy.alloc(3),
+                Some(Segment {
+                    offset: 64,
+                    length: 3
+                })
+            );
+            assert_eq!(buddy.alloc(2).map(|segment| segment.offset), Some(68));
+            assert_eq!(buddy.alloc(8).map(|segment| segment.offset), Some(72));
+            assert_eq!(buddy.alloc(4), None);
+            assert_eq!(buddy.internal_fragmentation(), 1);
+        }
+
+        #[test]
+        fn splits_capacity_into_power_of_two_roots() {
+            let mut buddy = BuddyAllocator::new(0, 960);
+            assert_eq!(buddy.max_request(), 512);
+            assert_eq!(buddy.alloc(600), None);
+            assert_eq!(buddy.alloc(200).map(|segment| segment.offset), Some(512));
+        }
+    }
+
+    mod free {
+        use super::*;
+
+        #[test]
+        fn coalesces_buddies() {
+            let mut buddy = BuddyAllocator::new(0, 16);
+            let first = buddy.alloc(4).unwrap();
+            let second = buddy.alloc(4).unwrap();
+            buddy.free(first);
+            assert_eq!(buddy.free_space().holes, 2);
+            buddy.free(second);
+            assert_eq!(buddy, BuddyAllocator::new(0, 16));
+        }
+
+        #[test]
+        fn keeps_roots_apart() {
+            let mut buddy = BuddyAllocator::new(0, 384);
+            let segments = (0..3)
+                .map(|_| buddy.alloc(128).unwrap())
+                .collect::<Vec<_>>();
+            let offsets = segments
+                .iter()
+                .map(|segment| segment.offset)
+                .collect::<Vec<_>>();
+            assert_eq!(offsets, vec![256, 0, 128]);
+            for segment in segments {
+                buddy.free(segment);
+            }
+            assert_eq!(buddy, BuddyAllocator::new(0, 384));
+            assert_eq!(buddy.free_space().largest_hole, 256);
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::str::FromStr;
+
+use super::segment_list::{FreeSpace, Segment};
+
+/// How processes waiting for a fixed partition are queued.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum FixedQueueing {
+    /// Every waiting process takes the smallest free partition that fits.
+    #[default]
+    Shared,
+    /// Every process waits for the smallest partition size that fits it,
+    /// behind the processes that arrived before it for that size.
+    PerPartition,
+}
+
+impl FromStr for FixedQueueing {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "shared" => Ok(FixedQueueing::Shared),
+            "per-partition" => Ok(FixedQueueing::PerPartition),
+            _ => Err(format!(
+                "unknown fixed partition queueing `{}`, expected one of shared, per-partition",
+                name
+            )),
+        }
+    }
+}
+
+/// A region pre-split into partitions of fixed sizes, each holding at most
+/// one process (MFT).
+#[derive(Debug, PartialEq)]
+pub struct FixedPartitions {
+    /// Every partition, in offset order.
+    partitions: Vec<Segment>,
+    /// Requested length of the process held by each partition.
+    used: Vec<Option<usize>>,
+    queueing: FixedQueueing,
+}
+
+impl FixedPartitions {
+    /// Lays out partitions of `sizes` one after the other from `base`.
+    pub fn new(base: usize, sizes: &[usize], queueing: FixedQueueing) -> Self {
+        let mut offset = base;
+        let partitions = sizes
+            .iter()
+            .map(|size| {
+                let partition = Segment {
+                    offset,
+                    length: *size,
+                };
+                offset += size;
+                partition
+            })
+            .collect::<Vec<_>>();
+        FixedPartitions {
+            used: vec![None; partitions.len()],
+            partitions,
+            queueing,
+        }
+    }
+
+    pub fn max_request(&self) -> usize {
+        self.partitions
+            .iter()
+            .map(|partition| partition.length)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Size of the smallest partition that could ever hold `length`.
+    fn fitting_size(&self, length: usize) -> Option<usize> {
+        self.partitions
+            .iter()
+            .map(|partition| partition.length)
+            .filter(|size| *size >= length)
+            .min()
+    }
+
+    /// The queue a process of `length` waits in with per-partition queues,
+    /// identified by the offset of the first partition of its size.
+    pub fn queue(&self, length: usize) -> Option<usize> {
+        if self.queueing == FixedQueueing::Shared {
+            return None;
+        }
+        let size = self.fitting_size(length)?;
+        self.partitions
+            .iter()
+            .find(|partition| partition.length == size)
+            .map(|partition| partition.offset)
+    }
+
+    /// Takes the smallest free partition holding `length`, the lowest on
+    /// ties; with per-partition queues only partitions of the smallest size
+    /// that fits are considered. The returned segment has the requested
+    /// length.
+    pub fn alloc(&mut self, length: usize) -> Option<Segment> {
+        let size = self.fitting_size(length)?;
+        let index = (0..self.partitions.len())
+            .filter(|index| self.used[*index].is_none())
+            .filter(|index| match self.queueing {
+                FixedQueueing::Shared => self.partitions[*index].length >= length,
+                FixedQueueing::PerPartition => self.partitions[*index].length == size,
+            })
+            .min_by_key(|index| self.partitions[*index].length)?;
+        self.used[index] = Some(length);
+        Some(Segment {
+            offset: self.partitions[index].offset,
+            length,
+        })
+    }
+
+    /// Gives the allocated `segment` a new `length` within its partition.
+    pub fn resize(&mut self, segment: &Segment, length: usize) -> bool {
+        match self
+            .partitions
+            .iter()
+            .position(|partition| partition.offset == segment.offset)
+        {
+            Some(index)
+                if self.used[index].is_some() && length <= self.partitions[index].length =>
+            {
+                self.used[index] = Some(length);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn free(&mut self, segment: Segment) {
+        if let Some(index) = self
+            .partitions
+            .iter()
+            .position(|partition| partition.offset == segment.offset)
+        {
+            self.used[index] = None;
+        }
+    }
+
+    pub fn free_space(&self) -> FreeSpace {
+        let free = self
+            .partitions
+            .iter()
+            .zip(self.used.iter())
+            .filter(|(_, used)| used.is_none())
+            .map(|(partition, _)| partition.length);
+        FreeSpace {
+            capacity: self
+                .partitions
+                .iter()
+                .map(|partition| partition.length)
+                .sum(),
+            free_blocks: free.clone().sum(),
+            holes: free.clone().count(),
+            largest_hole: free.max().unwrap_or(0),
+            internal_fragmentation: self
+                .partitions
+                .iter()
+                .zip(self.used.iter())
+                .filter_map(|(partition, used)| Some(partition.length - (*used)?))
+                .sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(partitions: &mut FixedPartitions, lengths: &[usize]) -> Vec<Option<usize>> {
+        lengths
+            .iter()
+            .map(|length| partitions.alloc(*length).map(|segment| segment.offset))
+            .collect()
+    }
+
+    mod alloc {
+        use super::*;
+
+        #[test]
+        fn takes_the_smallest_free_partition_that_fits() {
+            let mut partitions = FixedPartitions::new(10, &[8, 4, 8, 16], FixedQueueing::Shared);
+            assert_eq!(
+                offsets(&mut partitions, &[6, 3, 3, 7, 20]),
+                vec![Some(10), Some(18), Some(22), Some(30), None]
+            );
+            assert_eq!(partitions.max_request(), 16);
+        }
+
+        #[test]
+        fn per_partition_queues_wait_for_their_own_size() {
+            let mut partitions =
+                FixedPartitions::new(0, &[8, 4, 8, 16], FixedQueueing::PerPartition);
+            assert_eq!(
+                offsets(&mut partitions, &[3, 3, 6, 6, 6]),
+                vec![Some(8), None, Some(0), Some(12), None]
+            );
+            assert_eq!(partitions.queue(5), Some(0));
+            assert_eq!(partitions.queue(9), Some(20));
+            assert_eq!(partitions.queue(17), None);
+        }
+    }
+
+    mod free_space {
+        use super::*;
+
+        #[test]
+        fn counts_the_unused_part_of_partitions_as_internal_fragmentation() {
+            let mut partitions = FixedPartitions::new(0, &[8, 4, 16], FixedQueueing::Shared);
+            let segment = partitions.alloc(5).unwrap();
+            partitions.alloc(1).unwrap();
+            let free_space = partitions.free_space();
+            assert_eq!(free_space.capacity, 28);
+            assert_eq!(free_space.free_blocks, 16);
+            assert_eq!(free_space.holes, 1);
+            assert_eq!(free_space.internal_fragmentation, 3 + 3);
+            partitions.free(segment);
+            assert_eq!(partitions.free_space().internal_fragmentation, 3);
+            assert_eq!(partitions.free_space().largest_hole, 16);
+        }
+    }
+}
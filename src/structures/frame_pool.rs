@@ -0,0 +1,102 @@
+use std::collections::BTreeSet;
+
+use super::segment_list::FreeSpace;
+
+/// Fixed-size frames over `[base, base + capacity)`, handed out one by one
+/// with no need to be contiguous. Trailing blocks that do not fill a frame
+/// are never used.
+#[derive(Debug, PartialEq)]
+pub struct FramePool {
+    base: usize,
+    capacity: usize,
+    frame_size: usize,
+    /// Indexes of the free frames.
+    free: BTreeSet<usize>,
+}
+
+impl FramePool {
+    pub fn new(base: usize, capacity: usize, frame_size: usize) -> Self {
+        FramePool {
+            base,
+            capacity,
+            frame_size,
+            free: (0..capacity / frame_size).collect(),
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.capacity / self.frame_size
+    }
+
+    /// Takes the `count` free frames with the lowest offsets, or none at all.
+    pub fn alloc(&mut self, count: usize) -> Option<Vec<usize>> {
+        if self.free.len() < count {
+            return None;
+        }
+        let frames = (0..count)
+            .map(|_| self.free.pop_first().unwrap())
+            .map(|index| self.base + index * self.frame_size)
+            .collect();
+        Some(frames)
+    }
+
+    /// Returns frames given by `alloc`, identified by their offsets.
+    pub fn free(&mut self, frames: &[usize]) {
+        for offset in frames {
+            self.free.insert((offset - self.base) / self.frame_size);
+        }
+    }
+
+    /// Runs of adjacent free frames count as one hole.
+    pub fn free_space(&self) -> FreeSpace {
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for index in self.free.iter() {
+            match runs.last_mut() {
+                Some((start, length)) if *start + *length == *index => *length += 1,
+                _ => runs.push((*index, 1)),
+            }
+        }
+        FreeSpace {
+            capacity: self.capacity,
+            free_blocks: self.free.len() * self.frame_size,
+            holes: runs.len(),
+            largest_hole: runs.iter().map(|(_, length)| length).max().unwrap_or(&0)
+                * self.frame_size,
+            internal_fragmentation: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod alloc {
+        use super::*;
+
+        #[test]
+        fn takes_lowest_free_frames() {
+            let mut frame_pool = FramePool::new(64, 70, 16);
+            assert_eq!(frame_pool.frames(), 4);
+            assert_eq!(frame_pool.alloc(2), Some(vec![64, 80]));
+            assert_eq!(frame_pool.alloc(3), None);
+            frame_pool.free(&[64]);
+            assert_eq!(frame_pool.alloc(2), Some(vec![64, 96]));
+        }
+    }
+
+    mod free_space {
+        use super::*;
+
+        #[test]
+        fn counts_runs_of_free_frames() {
+            let mut frame_pool = FramePool::new(0, 64, 8);
+            let frames = frame_pool.alloc(4).unwrap();
+            frame_pool.free(&frames[1..2]);
+            let free_space = frame_pool.free_space();
+            assert_eq!(free_space.free_blocks, 40);
+            assert_eq!(free_space.holes, 2);
+            assert_eq!(free_space.largest_hole, 32);
+        }
+    }
+}
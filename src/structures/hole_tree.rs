@@ -0,0 +1,266 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// Free segments keyed by offset, each subtree knowing its longest segment,
+/// so that the lowest fitting segment is found in logarithmic time.
+///
+/// A treap: ordered by offset as a search tree and by a pseudo-random
+/// priority as a heap, which keeps it balanced with high probability.
+pub struct HoleTree {
+    root: Option<Box<Node>>,
+    len: usize,
+    /// Xorshift state the node priorities are drawn from.
+    seed: u64,
+}
+
+struct Node {
+    offset: usize,
+    length: usize,
+    priority: u64,
+    /// Longest length in the subtree rooted here.
+    max_length: usize,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+}
+
+impl Node {
+    fn update(&mut self) {
+        self.max_length = self
+            .length
+            .max(max_length(&self.left))
+            .max(max_length(&self.right));
+    }
+}
+
+fn max_length(node: &Option<Box<Node>>) -> usize {
+    node.as_ref().map_or(0, |node| node.max_length)
+}
+
+/// Splits `node` into the segments below `offset` and the ones from it.
+fn split(node: Option<Box<Node>>, offset: usize) -> (Option<Box<Node>>, Option<Box<Node>>) {
+    let Some(mut node) = node else {
+        return (None, None);
+    };
+    if node.offset < offset {
+        let (left, right) = split(node.right.take(), offset);
+        node.right = left;
+        node.update();
+        (Some(node), right)
+    } else {
+        let (left, right) = split(node.left.take(), offset);
+        node.left = right;
+        node.update();
+        (left, Some(node))
+    }
+}
+
+/// Joins two trees, every offset of `left` being below those of `right`.
+fn merge(left: Option<Box<Node>>, right: Option<Box<Node>>) -> Option<Box<Node>> {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+    }
+}
+
+/// Lowest segment of the subtree starting at `from` or later with at least
+/// `length` blocks.
+fn first_fit(node: &Option<Box<Node>>, from: usize, length: usize) -> Option<(usize, usize)> {
+    let node = node.as_ref()?;
+    if node.max_length < length {
+        return None;
+    }
+    if node.offset >= from {
+        if let Some(found) = first_fit(&node.left, from, length) {
+            return Some(found);
+        }
+        if node.length >= length {
+            return Some((node.offset, node.length));
+        }
+    }
+    first_fit(&node.right, from, length)
+}
+
+impl HoleTree {
+    pub fn new() -> Self {
+        HoleTree {
+            root: None,
+            len: 0,
+            seed: 0x9e37_79b9_7f4a_7c15,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn next_priority(&mut self) -> u64 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+
+    /// Adds the segment at `offset`, which must not be in the tree yet.
+    pub fn insert(&mut self, offset: usize, length: usize) {
+        let node = Box::new(Node {
+            offset,
+            length,
+            priority: self.next_priority(),
+            max_length: length,
+            left: None,
+            right: None,
+        });
+        let (left, right) = split(self.root.take(), offset);
+        self.root = merge(merge(left, Some(node)), right);
+        self.len += 1;
+    }
+
+    /// Takes out the segment at `offset`, returning its length.
+    pub fn remove(&mut self, offset: usize) -> Option<usize> {
+        let (left, right) = split(self.root.take(), offset);
+        let (found, right) = split(right, offset + 1);
+        self.root = merge(left, right);
+        let found = found?;
+        self.len -= 1;
+        Some(found.length)
+    }
+
+    /// The segment with the highest offset not above `offset`.
+    pub fn floor(&self, offset: usize) -> Option<(usize, usize)> {
+        let mut node = self.root.as_ref();
+        let mut found = None;
+        while let Some(current) = node {
+            match current.offset.cmp(&offset) {
+                Ordering::Equal => return Some((current.offset, current.length)),
+                Ordering::Less => {
+                    found = Some((current.offset, current.length));
+                    node = current.right.as_ref();
+                }
+                Ordering::Greater => node = current.left.as_ref(),
+            }
+        }
+        found
+    }
+
+    /// The segment with the lowest offset above `offset`.
+    pub fn after(&self, offset: usize) -> Option<(usize, usize)> {
+        let mut node = self.root.as_ref();
+        let mut found = None;
+        while let Some(current) = node {
+            if current.offset > offset {
+                found = Some((current.offset, current.length));
+                node = current.left.as_ref();
+            } else {
+                node = current.right.as_ref();
+            }
+        }
+        found
+    }
+
+    /// The segment with the lowest offset from `from` on holding `length`.
+    pub fn first_fit(&self, from: usize, length: usize) -> Option<(usize, usize)> {
+        first_fit(&self.root, from, length)
+    }
+
+    /// Every segment, as `(offset, length)`, in offset order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let mut stack = Vec::new();
+        let mut node = self.root.as_deref();
+        std::iter::from_fn(move || {
+            while let Some(current) = node {
+                stack.push(current);
+                node = current.left.as_deref();
+            }
+            let current = stack.pop()?;
+            node = current.right.as_deref();
+            Some((current.offset, current.length))
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+}
+
+impl Default for HoleTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PartialEq for HoleTree {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl fmt::Debug for HoleTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_tree(segments: &[(usize, usize)]) -> HoleTree {
+        let mut tree = HoleTree::new();
+        for (offset, length) in segments {
+            tree.insert(*offset, *length);
+        }
+        tree
+    }
+
+    #[test]
+    fn keeps_segments_in_offset_order() {
+        let mut tree = create_tree(&[(30, 2), (0, 5), (10, 8), (20, 1)]);
+        assert_eq!(
+            tree.iter().collect::<Vec<_>>(),
+            vec![(0, 5), (10, 8), (20, 1), (30, 2)]
+        );
+        assert_eq!(tree.remove(10), Some(8));
+        assert_eq!(tree.remove(11), None);
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.floor(25), Some((20, 1)));
+        assert_eq!(tree.floor(20), Some((20, 1)));
+        assert_eq!(tree.after(20), Some((30, 2)));
+        assert_eq!(tree.after(30), None);
+    }
+
+    #[test]
+    fn finds_the_lowest_fitting_segment() {
+        let tree = create_tree(&[(0, 2), (10, 8), (20, 3), (30, 8), (40, 9)]);
+        assert_eq!(tree.first_fit(0, 3), Some((10, 8)));
+        assert_eq!(tree.first_fit(11, 8), Some((30, 8)));
+        assert_eq!(tree.first_fit(0, 9), Some((40, 9)));
+        assert_eq!(tree.first_fit(41, 1), None);
+        assert_eq!(tree.first_fit(0, 10), None);
+    }
+
+    #[test]
+    fn stays_shallow_with_sorted_insertions() {
+        let tree = create_tree(&(0..10_000).map(|index| (index * 2, 1)).collect::<Vec<_>>());
+        fn depth(node: &Option<Box<Node>>) -> usize {
+            node.as_ref()
+                .map_or(0, |node| 1 + depth(&node.left).max(depth(&node.right)))
+        }
+        assert!(depth(&tree.root) < 64);
+    }
+}
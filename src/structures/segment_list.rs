@@ -1,97 +1,387 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use super::hole_tree::HoleTree;
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Segment {
     pub offset: usize,
     pub length: usize,
 }
 
+/// Which free segment `SegmentList::alloc` carves a new segment from.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub enum AllocationPolicy {
+    /// The free segment with the lowest offset that fits.
+    #[default]
+    FirstFit,
+    /// Like first fit, but searching from the free segment holding the end
+    /// of the previous allocation, or the first one after it.
+    NextFit,
+    /// The smallest free segment that fits.
+    BestFit,
+    /// The largest free segment.
+    WorstFit,
+}
+
+impl FromStr for AllocationPolicy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "first-fit" => Ok(AllocationPolicy::FirstFit),
+            "next-fit" => Ok(AllocationPolicy::NextFit),
+            "best-fit" => Ok(AllocationPolicy::BestFit),
+            "worst-fit" => Ok(AllocationPolicy::WorstFit),
+            _ => Err(format!(
+                "unknown allocation policy `{}`, expected one of first-fit, next-fit, best-fit, worst-fit",
+                name
+            )),
+        }
+    }
+}
+
+/// Snapshot of the free space of a `SegmentList`.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct FreeSpace {
+    pub capacity: usize,
+    pub free_blocks: usize,
+    pub holes: usize,
+    pub largest_hole: usize,
+    /// Blocks allocated beyond what was requested.
+    pub internal_fragmentation: usize,
+}
+
+impl FreeSpace {
+    /// External fragmentation index, `1 - largest_hole / free_blocks`: 0 when
+    /// the free space is contiguous, close to 1 when it is scattered.
+    pub fn fragmentation(&self) -> f64 {
+        match self.free_blocks {
+            0 => 0.0,
+            free_blocks => 1.0 - self.largest_hole as f64 / free_blocks as f64,
+        }
+    }
+
+    /// Fraction of the capacity in use.
+    pub fn occupancy(&self) -> f64 {
+        match self.capacity {
+            0 => 0.0,
+            capacity => (capacity - self.free_blocks) as f64 / capacity as f64,
+        }
+    }
+}
+
+/// What sliding the allocated segments of a `SegmentList` together took.
+#[derive(Debug, Default, PartialEq, Clone, Copy)]
+pub struct Compaction {
+    pub moved_blocks: usize,
+    /// Segments that got a new offset.
+    pub relocated: usize,
+}
+
+/// Free segments of a region, merged when adjacent.
+///
+/// The holes are indexed by offset, to find neighbours and the lowest one
+/// that fits, and by length, to find the smallest and the largest one, so
+/// allocating and freeing take logarithmic time.
 #[derive(Debug, PartialEq)]
 pub struct SegmentList {
-    segments: Vec<Segment>,
+    holes: HoleTree,
+    /// `(length, offset)` of every hole.
+    sizes: BTreeSet<(usize, usize)>,
+    free_blocks: usize,
+    policy: AllocationPolicy,
+    /// Offset where the last allocation ended, used by next fit.
+    cursor: usize,
+    /// Length of the segments the list was created with.
+    capacity: usize,
+    /// Lowest offset and end of the segments the list was created with.
+    bounds: (usize, usize),
+    /// Freed segments that were already partly free.
+    double_frees: Vec<Segment>,
 }
 
 impl SegmentList {
     pub fn new(segments: Vec<Segment>) -> SegmentList {
-        let mut segment_list = SegmentList { segments: vec![] };
+        SegmentList::with_policy(segments, AllocationPolicy::default())
+    }
+
+    pub fn with_policy(segments: Vec<Segment>, policy: AllocationPolicy) -> SegmentList {
+        let mut segment_list = SegmentList {
+            holes: HoleTree::new(),
+            sizes: BTreeSet::new(),
+            free_blocks: 0,
+            policy,
+            cursor: 0,
+            capacity: segments.iter().map(|segment| segment.length).sum(),
+            bounds: (
+                segments
+                    .iter()
+                    .map(|segment| segment.offset)
+                    .min()
+                    .unwrap_or(0),
+                segments
+                    .iter()
+                    .map(|segment| segment.offset + segment.length)
+                    .max()
+                    .unwrap_or(0),
+            ),
+            double_frees: Vec::new(),
+        };
         for segment in segments {
             segment_list.free(segment);
         }
         segment_list
     }
 
-    pub fn free(&mut self, to_free: Segment) {
-        let index = self
-            .segments
+    pub fn holes(&self) -> usize {
+        self.holes.len()
+    }
+
+    pub fn largest_hole(&self) -> usize {
+        self.sizes.last().map_or(0, |(length, _)| *length)
+    }
+
+    pub fn free_blocks(&self) -> usize {
+        self.free_blocks
+    }
+
+    pub fn fragmentation(&self) -> f64 {
+        self.free_space().fragmentation()
+    }
+
+    pub fn free_space(&self) -> FreeSpace {
+        FreeSpace {
+            capacity: self.capacity,
+            free_blocks: self.free_blocks(),
+            holes: self.holes(),
+            largest_hole: self.largest_hole(),
+            internal_fragmentation: 0,
+        }
+    }
+
+    /// Every hole, in offset order.
+    pub fn free_segments(&self) -> Vec<Segment> {
+        self.holes
             .iter()
-            .position(|segment| segment.offset > to_free.offset)
-            .unwrap_or(self.segments.len());
-        let has_to_merge_left = index > 0
-            && self.segments[index - 1].offset + self.segments[index - 1].length >= to_free.offset;
-        let has_to_merge_right = index < self.segments.len()
-            && to_free.offset + to_free.length >= self.segments[index].offset;
-        match (has_to_merge_left, has_to_merge_right) {
-            (false, false) => {
-                self.segments.insert(index, to_free);
-            }
-            (true, false) => {
-                self.segments[index - 1].length =
-                    to_free.offset + to_free.length - self.segments[index - 1].offset;
+            .map(|(offset, length)| Segment { offset, length })
+            .collect()
+    }
+
+    fn insert_hole(&mut self, offset: usize, length: usize) {
+        self.holes.insert(offset, length);
+        self.sizes.insert((length, offset));
+        self.free_blocks += length;
+    }
+
+    fn remove_hole(&mut self, offset: usize) {
+        if let Some(length) = self.holes.remove(offset) {
+            self.sizes.remove(&(length, offset));
+            self.free_blocks -= length;
+        }
+    }
+
+    pub fn free(&mut self, to_free: Segment) {
+        if to_free.length == 0 {
+            return;
+        }
+        let left = self.holes.floor(to_free.offset);
+        let right = self.holes.after(to_free.offset);
+        let has_to_merge_left =
+            matches!(left, Some((offset, length)) if offset + length >= to_free.offset);
+        let has_to_merge_right =
+            matches!(right, Some((offset, _)) if to_free.offset + to_free.length >= offset);
+        let overlaps_left =
+            matches!(left, Some((offset, length)) if offset + length > to_free.offset);
+        let overlaps_right =
+            matches!(right, Some((offset, _)) if to_free.offset + to_free.length > offset);
+        if overlaps_left || overlaps_right {
+            self.double_frees.push(to_free.clone());
+        }
+        match (left, right) {
+            (Some((left_offset, _)), Some((right_offset, right_length)))
+                if has_to_merge_left && has_to_merge_right =>
+            {
+                self.remove_hole(left_offset);
+                self.remove_hole(right_offset);
+                self.insert_hole(left_offset, right_offset + right_length - left_offset);
             }
-            (false, true) => {
-                self.segments[index].length =
-                    self.segments[index].offset + self.segments[index].length - to_free.offset;
-                self.segments[index].offset = to_free.offset;
+            (Some((left_offset, _)), _) if has_to_merge_left => {
+                self.remove_hole(left_offset);
+                self.insert_hole(left_offset, to_free.offset + to_free.length - left_offset);
             }
-            (true, true) => {
-                self.segments[index - 1].length = self.segments[index].offset
-                    + self.segments[index].length
-                    - self.segments[index - 1].offset;
-                self.segments.remove(index);
+            (_, Some((right_offset, right_length))) if has_to_merge_right => {
+                self.remove_hole(right_offset);
+                self.insert_hole(to_free.offset, right_offset + right_length - to_free.offset);
             }
+            _ => self.insert_hole(to_free.offset, to_free.length),
         }
     }
 
     pub fn alloc_segment(&mut self, to_remove: &Segment) -> Option<()> {
-        let alloc_index = self.segments.iter().position(|s| {
-            s.offset <= to_remove.offset
-                && s.offset + s.length >= to_remove.offset + to_remove.length
-        });
-        match alloc_index {
-            None => return None,
-            Some(index) => {
-                let left_remaining = Segment {
-                    offset: self.segments[index].offset,
-                    length: to_remove.offset - self.segments[index].offset,
-                };
-                let right_remaining = Segment {
-                    offset: to_remove.offset + to_remove.length,
-                    length: self.segments[index].offset + self.segments[index].length
-                        - to_remove.offset
-                        - to_remove.length,
-                };
-                if left_remaining.length == 0 && right_remaining.length == 0 {
-                    self.segments.remove(index);
-                } else if left_remaining.length == 0 {
-                    self.segments[index] = right_remaining;
-                } else if right_remaining.length == 0 {
-                    self.segments[index] = left_remaining;
-                } else {
-                    self.segments[index] = left_remaining;
-                    self.segments.insert(index + 1, right_remaining);
-                }
-                return Some(());
-            }
-        };
+        let (offset, length) = self
+            .holes
+            .floor(to_remove.offset)
+            .filter(|(offset, length)| offset + length >= to_remove.offset + to_remove.length)?;
+        let left_remaining = to_remove.offset - offset;
+        let right_remaining = offset + length - to_remove.offset - to_remove.length;
+        self.remove_hole(offset);
+        if left_remaining > 0 {
+            self.insert_hole(offset, left_remaining);
+        }
+        if right_remaining > 0 {
+            self.insert_hole(to_remove.offset + to_remove.length, right_remaining);
+        }
+        Some(())
     }
 
     pub fn alloc(&mut self, length: usize) -> Option<Segment> {
-        let original_segment = self.segments.iter().find(|s| s.length >= length)?;
-        let new_segment = Segment {
-            offset: original_segment.offset,
-            length,
+        let offset = match self.policy {
+            AllocationPolicy::FirstFit => self.holes.first_fit(0, length)?.0,
+            AllocationPolicy::NextFit => {
+                self.holes
+                    .floor(self.cursor)
+                    .filter(|(offset, hole_length)| {
+                        offset + hole_length > self.cursor && *hole_length >= length
+                    })
+                    .or_else(|| self.holes.first_fit(self.cursor, length))
+                    .or_else(|| self.holes.first_fit(0, length))?
+                    .0
+            }
+            AllocationPolicy::BestFit => self.sizes.range((length, 0)..).next()?.1,
+            AllocationPolicy::WorstFit => {
+                let largest = self.largest_hole();
+                if largest < length {
+                    return None;
+                }
+                self.sizes.range((largest, 0)..).next()?.1
+            }
         };
+        let new_segment = Segment { offset, length };
         self.alloc_segment(&new_segment)?;
+        self.cursor = new_segment.offset + new_segment.length;
         Some(new_segment)
     }
+
+    /// Checks that the holes are sorted, non-empty, apart from each other and
+    /// within the segments the list was created with, that the indexes agree
+    /// and that no segment was freed twice, describing the first problem.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if let Some(segment) = self.double_frees.first() {
+            return Err(format!(
+                "blocks {}..{} were freed while partly free",
+                segment.offset,
+                segment.offset + segment.length
+            ));
+        }
+        let (start, end) = self.bounds;
+        let mut previous_end = None;
+        for (offset, length) in self.holes.iter() {
+            if length == 0 {
+                return Err(format!("empty hole at {}", offset));
+            }
+            if offset < start || offset + length > end {
+                return Err(format!(
+                    "hole {}..{} is out of {}..{}",
+                    offset,
+                    offset + length,
+                    start,
+                    end
+                ));
+            }
+            match previous_end {
+                Some(previous_end) if previous_end > offset => {
+                    return Err(format!(
+                        "hole at {} overlaps the one ending at {}",
+                        offset, previous_end
+                    ))
+                }
+                Some(previous_end) if previous_end == offset => {
+                    return Err(format!("holes meeting at {} are not merged", offset))
+                }
+                _ => {}
+            }
+            if !self.sizes.contains(&(length, offset)) {
+                return Err(format!("hole at {} is missing from the size index", offset));
+            }
+            previous_end = Some(offset + length);
+        }
+        if self.sizes.len() != self.holes.len() {
+            return Err(format!(
+                "{} holes but {} entries in the size index",
+                self.holes.len(),
+                self.sizes.len()
+            ));
+        }
+        let free_blocks = self.holes.iter().map(|(_, length)| length).sum::<usize>();
+        if free_blocks != self.free_blocks || free_blocks > self.capacity {
+            return Err(format!(
+                "{} free blocks counted as {} out of {}",
+                free_blocks, self.free_blocks, self.capacity
+            ));
+        }
+        Ok(())
+    }
+
+    /// Gives the allocated `segment` a new `length` without moving it:
+    /// shrinking frees its tail, growing takes the blocks right after it,
+    /// which must be free.
+    pub fn resize(&mut self, segment: &Segment, length: usize) -> bool {
+        match length.cmp(&segment.length) {
+            Ordering::Less => {
+                self.free(Segment {
+                    offset: segment.offset + length,
+                    length: segment.length - length,
+                });
+                true
+            }
+            Ordering::Equal => true,
+            Ordering::Greater => self
+                .alloc_segment(&Segment {
+                    offset: segment.offset + segment.length,
+                    length: length - segment.length,
+                })
+                .is_some(),
+        }
+    }
+
+    /// Slides `allocated` towards the lowest offset, in offset order, leaving
+    /// a single hole at the end. `allocated` must be every segment taken from
+    /// the list, otherwise nothing is moved and `None` is returned.
+    pub fn compact(&mut self, allocated: &mut [&mut Segment]) -> Option<Compaction> {
+        let allocated_blocks = allocated
+            .iter()
+            .map(|segment| segment.length)
+            .sum::<usize>();
+        if allocated_blocks + self.free_blocks() != self.capacity {
+            return None;
+        }
+        let base = allocated
+            .iter()
+            .map(|segment| segment.offset)
+            .chain(self.holes.iter().map(|(offset, _)| offset))
+            .min()?;
+        allocated.sort_by_key(|segment| segment.offset);
+        let mut compaction = Compaction::default();
+        let mut next_offset = base;
+        for segment in allocated.iter_mut() {
+            if segment.offset != next_offset {
+                compaction.moved_blocks += segment.length;
+                compaction.relocated += 1;
+                segment.offset = next_offset;
+            }
+            next_offset += segment.length;
+        }
+        self.holes.clear();
+        self.sizes.clear();
+        self.free_blocks = 0;
+        if allocated_blocks < self.capacity {
+            self.insert_hole(next_offset, self.capacity - allocated_blocks);
+        }
+        self.cursor = next_offset;
+        Some(compaction)
+    }
 }
 
 #[cfg(test)]
@@ -108,7 +398,7 @@ mod tests {
                 length: 10,
             }]);
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![Segment {
                     offset: 0,
                     length: 10
@@ -129,7 +419,7 @@ mod tests {
                 },
             ]);
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![Segment {
                     offset: 0,
                     length: 20
@@ -152,7 +442,7 @@ mod tests {
                 length: 10,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![
                     Segment {
                         offset: 0,
@@ -183,7 +473,7 @@ mod tests {
                 length: 5,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![
                     Segment {
                         offset: 0,
@@ -214,7 +504,7 @@ mod tests {
                 length: 5,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![
                     Segment {
                         offset: 0,
@@ -245,7 +535,7 @@ mod tests {
                 length: 10,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![Segment {
                     offset: 0,
                     length: 30
@@ -254,6 +544,49 @@ mod tests {
         }
     }
 
+    mod free_space {
+        use super::*;
+
+        #[test]
+        fn test_fragmented_space() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 0,
+                length: 10,
+            }]);
+            segment_list.alloc_segment(&Segment {
+                offset: 2,
+                length: 2,
+            });
+            segment_list.alloc_segment(&Segment {
+                offset: 8,
+                length: 2,
+            });
+            assert_eq!(
+                segment_list.free_space(),
+                FreeSpace {
+                    capacity: 10,
+                    free_blocks: 6,
+                    holes: 2,
+                    largest_hole: 4,
+                    internal_fragmentation: 0,
+                }
+            );
+            assert!((segment_list.fragmentation() - 1.0 / 3.0).abs() < 1e-9);
+            assert!((segment_list.free_space().occupancy() - 0.4).abs() < 1e-9);
+        }
+
+        #[test]
+        fn test_full_list_is_not_fragmented() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 0,
+                length: 4,
+            }]);
+            segment_list.alloc(4);
+            assert_eq!(segment_list.holes(), 0);
+            assert_eq!(segment_list.fragmentation(), 0.0);
+        }
+    }
+
     mod alloc_segment {
         use super::*;
 
@@ -278,7 +611,7 @@ mod tests {
                 length: 5,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![
                     Segment {
                         offset: 0,
@@ -303,7 +636,7 @@ mod tests {
                 length: 5,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![Segment {
                     offset: 0,
                     length: 5
@@ -322,7 +655,7 @@ mod tests {
                 length: 5,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![Segment {
                     offset: 5,
                     length: 5
@@ -341,7 +674,7 @@ mod tests {
                 length: 2,
             });
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![
                     Segment {
                         offset: 0,
@@ -367,7 +700,7 @@ mod tests {
             });
             assert_eq!(result, None);
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![Segment {
                     offset: 0,
                     length: 10
@@ -387,7 +720,7 @@ mod tests {
             });
             assert_eq!(result, None);
             assert_eq!(
-                segment_list.segments,
+                segment_list.free_segments(),
                 vec![Segment {
                     offset: 0,
                     length: 10
@@ -451,6 +784,92 @@ mod tests {
             );
         }
 
+        fn create_fragmented_list(policy: AllocationPolicy) -> SegmentList {
+            SegmentList::with_policy(
+                vec![
+                    Segment {
+                        offset: 0,
+                        length: 6,
+                    },
+                    Segment {
+                        offset: 10,
+                        length: 3,
+                    },
+                    Segment {
+                        offset: 20,
+                        length: 8,
+                    },
+                ],
+                policy,
+            )
+        }
+
+        fn alloc_offsets(segment_list: &mut SegmentList, lengths: &[usize]) -> Vec<usize> {
+            lengths
+                .iter()
+                .map(|length| segment_list.alloc(*length).unwrap().offset)
+                .collect()
+        }
+
+        #[test]
+        fn test_alloc_next_fit() {
+            let mut segment_list = create_fragmented_list(AllocationPolicy::NextFit);
+            assert_eq!(
+                alloc_offsets(&mut segment_list, &[3, 3, 3, 3]),
+                vec![0, 3, 10, 20]
+            );
+            segment_list.free(Segment {
+                offset: 0,
+                length: 3,
+            });
+            assert_eq!(alloc_offsets(&mut segment_list, &[3, 3]), vec![23, 0]);
+        }
+
+        #[test]
+        fn test_alloc_next_fit_from_the_hole_holding_the_cursor() {
+            let mut segment_list = SegmentList::with_policy(
+                vec![
+                    Segment {
+                        offset: 0,
+                        length: 4,
+                    },
+                    Segment {
+                        offset: 10,
+                        length: 10,
+                    },
+                ],
+                AllocationPolicy::NextFit,
+            );
+            assert_eq!(alloc_offsets(&mut segment_list, &[4, 4]), vec![0, 10]);
+            segment_list.free(Segment {
+                offset: 10,
+                length: 4,
+            });
+            segment_list.free(Segment {
+                offset: 0,
+                length: 4,
+            });
+            assert_eq!(alloc_offsets(&mut segment_list, &[4, 4]), vec![10, 14]);
+        }
+
+        #[test]
+        fn test_alloc_best_fit() {
+            let mut segment_list = create_fragmented_list(AllocationPolicy::BestFit);
+            assert_eq!(
+                alloc_offsets(&mut segment_list, &[3, 5, 2]),
+                vec![10, 0, 20]
+            );
+        }
+
+        #[test]
+        fn test_alloc_worst_fit() {
+            let mut segment_list = create_fragmented_list(AllocationPolicy::WorstFit);
+            assert_eq!(
+                alloc_offsets(&mut segment_list, &[3, 3, 2]),
+                vec![20, 0, 23]
+            );
+        }
+
         #[test]
         fn test_alloc_fail() {
             let mut segment_list = SegmentList::new(vec![
@@ -471,4 +890,273 @@ mod tests {
             assert_eq!(result, None);
         }
     }
+
+    mod resize {
+        use super::*;
+
+        #[test]
+        fn grows_into_the_adjacent_hole_only() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 0,
+                length: 10,
+            }]);
+            let first = segment_list.alloc(4).unwrap();
+            let second = segment_list.alloc(2).unwrap();
+            assert!(!segment_list.resize(&first, 5));
+            assert!(segment_list.resize(&second, 5));
+            assert_eq!(
+                segment_list.free_segments(),
+                vec![Segment {
+                    offset: 9,
+                    length: 1
+                }]
+            );
+            assert!(!segment_list.resize(&second, 7));
+        }
+
+        #[test]
+        fn shrinking_frees_the_tail() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 0,
+                length: 10,
+            }]);
+            let segment = segment_list.alloc(10).unwrap();
+            assert!(segment_list.resize(&segment, 6));
+            assert_eq!(
+                segment_list.free_segments(),
+                vec![Segment {
+                    offset: 6,
+                    length: 4
+                }]
+            );
+        }
+    }
+
+    mod compact {
+        use super::*;
+
+        #[test]
+        fn merges_every_hole_at_the_end() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 10,
+                length: 30,
+            }]);
+            let first = segment_list.alloc(5).unwrap();
+            let mut second = segment_list.alloc(10).unwrap();
+            let mut third = segment_list.alloc(5).unwrap();
+            segment_list.free(first);
+            let compaction = segment_list.compact(&mut [&mut third, &mut second]);
+            assert_eq!(
+                compaction,
+                Some(Compaction {
+                    moved_blocks: 15,
+                    relocated: 2
+                })
+            );
+            assert_eq!((second.offset, third.offset), (10, 20));
+            assert_eq!(
+                segment_list.free_segments(),
+                vec![Segment {
+                    offset: 25,
+                    length: 15
+                }]
+            );
+        }
+
+        #[test]
+        fn refuses_when_a_segment_is_missing() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 0,
+                length: 10,
+            }]);
+            segment_list.alloc(2).unwrap();
+            let mut second = segment_list.alloc(2).unwrap();
+            assert_eq!(segment_list.compact(&mut [&mut second]), None);
+            assert_eq!(second.offset, 2);
+        }
+    }
+
+    mod check_invariants {
+        use super::*;
+
+        #[test]
+        fn flags_double_frees() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 0,
+                length: 10,
+            }]);
+            let segment = segment_list.alloc(4).unwrap();
+            segment_list.free(segment.clone());
+            assert_eq!(segment_list.check_invariants(), Ok(()));
+            segment_list.free(segment);
+            assert_eq!(
+                segment_list.check_invariants(),
+                Err(String::from("blocks 0..4 were freed while partly free"))
+            );
+        }
+
+        #[test]
+        fn flags_frees_out_of_bounds() {
+            let mut segment_list = SegmentList::new(vec![Segment {
+                offset: 10,
+                length: 10,
+            }]);
+            segment_list.alloc(10).unwrap();
+            segment_list.free(Segment {
+                offset: 18,
+                length: 4,
+            });
+            assert_eq!(
+                segment_list.check_invariants(),
+                Err(String::from("hole 18..22 is out of 10..20"))
+            );
+        }
+    }
+
+    /// Randomized operations checked against a bitmap of free blocks.
+    mod model {
+        use std::cmp::Reverse;
+
+        use super::*;
+
+        const BLOCKS: usize = 200;
+
+        struct Xorshift(u64);
+
+        impl Xorshift {
+            fn below(&mut self, bound: usize) -> usize {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                (self.0 % bound as u64) as usize
+            }
+        }
+
+        struct Bitmap {
+            free: Vec<bool>,
+            cursor: usize,
+        }
+
+        impl Bitmap {
+            /// Maximal runs of free blocks.
+            fn runs(&self) -> Vec<Segment> {
+                let mut runs: Vec<Segment> = Vec::new();
+                for (block, free) in self.free.iter().enumerate() {
+                    match runs.last_mut() {
+                        Some(run) if *free && run.offset + run.length == block => run.length += 1,
+                        _ if *free => runs.push(Segment {
+                            offset: block,
+                            length: 1,
+                        }),
+                        _ => {}
+                    }
+                }
+                runs
+            }
+
+            fn set(&mut self, segment: &Segment, free: bool) {
+                for block in segment.offset..segment.offset + segment.length {
+                    self.free[block] = free;
+                }
+            }
+
+            fn alloc(&mut self, policy: AllocationPolicy, length: usize) -> Option<Segment> {
+                let runs = self.runs();
+                let mut fitting = runs.iter().filter(|run| run.length >= length);
+                let run = match policy {
+                    AllocationPolicy::FirstFit => fitting.next(),
+                    AllocationPolicy::NextFit => {
+                        let cursor = self.cursor;
+                        let fitting = fitting.collect::<Vec<_>>();
+                        fitting
+                            .iter()
+                            .find(|run| run.offset + run.length > cursor)
+                            .or(fitting.first())
+                            .copied()
+                    }
+                    AllocationPolicy::BestFit => fitting.min_by_key(|run| run.length),
+                    AllocationPolicy::WorstFit => {
+                        fitting.max_by_key(|run| (run.length, Reverse(run.offset)))
+                    }
+                }?;
+                let segment = Segment {
+                    offset: run.offset,
+                    length,
+                };
+                self.set(&segment, false);
+                self.cursor = segment.offset + length;
+                Some(segment)
+            }
+        }
+
+        fn run_model(policy: AllocationPolicy, seed: u64) {
+            let mut random = Xorshift(seed);
+            let region = Segment {
+                offset: 0,
+                length: BLOCKS,
+            };
+            let mut segment_list = SegmentList::with_policy(vec![region], policy);
+            let mut bitmap = Bitmap {
+                free: vec![true; BLOCKS],
+                cursor: 0,
+            };
+            let mut allocated = Vec::new();
+            for step in 0..2_000 {
+                match random.below(4) {
+                    0 => {
+                        let length = random.below(24) + 1;
+                        let expected = bitmap.alloc(policy, length);
+                        assert_eq!(segment_list.alloc(length), expected, "step {}", step);
+                        allocated.extend(expected);
+                    }
+                    1 if !allocated.is_empty() => {
+                        let segment = allocated.swap_remove(random.below(allocated.len()));
+                        bitmap.set(&segment, true);
+                        segment_list.free(segment);
+                    }
+                    2 => segment_list.free(Segment {
+                        offset: random.below(BLOCKS + 1),
+                        length: 0,
+                    }),
+                    _ => {
+                        let offset = random.below(BLOCKS);
+                        let segment = Segment {
+                            offset,
+                            length: random.below(BLOCKS - offset).min(8) + 1,
+                        };
+                        let fits = bitmap.free[segment.offset..segment.offset + segment.length]
+                            .iter()
+                            .all(|free| *free);
+                        let result = segment_list.alloc_segment(&segment);
+                        assert_eq!(result.is_some(), fits, "step {}", step);
+                        if fits {
+                            bitmap.set(&segment, false);
+                            allocated.push(segment);
+                        }
+                    }
+                }
+                assert_eq!(segment_list.check_invariants(), Ok(()), "step {}", step);
+                let runs = bitmap.runs();
+                assert_eq!(
+                    segment_list.largest_hole(),
+                    runs.iter().map(|run| run.length).max().unwrap_or(0)
+                );
+                assert_eq!(segment_list.free_segments(), runs, "step {}", step);
+            }
+        }
+
+        #[test]
+        fn matches_a_bitmap_with_every_policy() {
+            for policy in [
+                AllocationPolicy::FirstFit,
+                AllocationPolicy::NextFit,
+                AllocationPolicy::BestFit,
+                AllocationPolicy::WorstFit,
+            ] {
+                for seed in 1..=8 {
+                    run_model(policy, seed * 0x9e37_79b9);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,324 @@
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+use crate::config::SwapConfig;
+use crate::events::SimEvent;
+use crate::memory::{AddressSpace, MemoryManager};
+use crate::process::Process;
+use crate::resources::ResourceManager;
+
+/// Memory of a victim being written to the swap area.
+struct SwapOut {
+    pid: usize,
+    priority: usize,
+    address_space: AddressSpace,
+    done_at: usize,
+}
+
+/// Moves processes blocked on a resource out of memory so that waiting
+/// arrivals can be created, and brings them back once they are unblocked.
+pub struct Swapper {
+    config: SwapConfig,
+    swapping_out: Option<SwapOut>,
+    /// Unblocked processes waiting for memory to be swapped back in.
+    waiting: VecDeque<Process>,
+    /// Processes being read back, with the tick they become ready at.
+    swapping_in: Vec<(usize, Process)>,
+}
+
+impl Swapper {
+    pub fn new(config: &SwapConfig) -> Self {
+        Swapper {
+            config: config.clone(),
+            swapping_out: None,
+            waiting: VecDeque::new(),
+            swapping_in: Vec::new(),
+        }
+    }
+
+    /// Whether a swapped process has still to be brought back.
+    pub fn has_processes(&self) -> bool {
+        !self.waiting.is_empty() || !self.swapping_in.is_empty()
+    }
+
+    /// Whether a victim is being written out, its memory still to be freed.
+    pub fn is_swapping_out(&self) -> bool {
+        self.swapping_out.is_some()
+    }
+
+    /// Memory held by the swapper: the victim being written out and the
+    /// processes being read back.
+    pub fn address_spaces_mut(&mut self) -> Vec<&mut AddressSpace> {
+        let mut address_spaces = self
+            .swapping_in
+            .iter_mut()
+            .map(|(_, process)| &mut process.address_space)
+            .collect::<Vec<_>>();
+        address_spaces.extend(
+            self.swapping_out
+                .as_mut()
+                .map(|swap_out| &mut swap_out.address_space),
+        );
+        address_spaces
+    }
+
+    /// Finishes the transfers due at `timestamp` and starts swapping in the
+    /// waiting processes that fit, returning the processes ready to run.
+    pub fn on_tick(
+        &mut self,
+        timestamp: usize,
+        memory_manager: &mut MemoryManager,
+        events: &mut Vec<SimEvent>,
+    ) -> Vec<Process> {
+        if let Some(swap_out) = self
+            .swapping_out
+            .take_if(|swap_out| swap_out.done_at <= timestamp)
+        {
+            events.push(SimEvent::SwappedOut {
+                pid: swap_out.pid,
+                priority: swap_out.priority,
+                blocks: memory_manager.resident_blocks(&swap_out.address_space),
+            });
+            memory_manager.free(swap_out.address_space);
+        }
+
+        let mut ready = Vec::new();
+        let mut index = 0;
+        while index < self.swapping_in.len() {
+            if self.swapping_in[index].0 <= timestamp {
+                let (_, process) = self.swapping_in.remove(index);
+                events.push(SimEvent::SwappedIn {
+                    pid: process.software_context.id,
+                    priority: process.software_context.priority,
+                    address_space: process.address_space.clone(),
+                });
+                ready.push(process);
+            } else {
+                index += 1;
+            }
+        }
+
+        for mut process in std::mem::take(&mut self.waiting) {
+            let request = memory_manager.request(&process.address_space);
+            match memory_manager.alloc_request(process.software_context.priority, &request) {
+                Ok(address_space) => {
+                    process.address_space = address_space;
+                    self.swapping_in
+                        .push((timestamp + self.config.swap_in_ticks, process));
+                }
+                Err(_) => self.waiting.push_back(process),
+            }
+        }
+        ready
+    }
+
+    /// Hands back an unblocked process that is still in memory; a swapped
+    /// one waits until it can be swapped in.
+    pub fn swap_in(&mut self, process: Process) -> Option<Process> {
+        match process.address_space {
+            AddressSpace::Swapped(_) => {
+                self.waiting.push_back(process);
+                None
+            }
+            _ => Some(process),
+        }
+    }
+
+    /// Starts swapping out the blocked process holding the most memory of
+    /// the partition `waiting` needs, if freeing every blocked process of
+    /// that partition would make room for it. One victim is written out at
+    /// a time.
+    pub fn swap_out(
+        &mut self,
+        waiting: Option<(usize, usize)>,
+        timestamp: usize,
+        memory_manager: &MemoryManager,
+        resource_manager: &mut ResourceManager,
+    ) {
+        let Some((priority, blocks)) = waiting else {
+            return;
+        };
+        if !self.config.enabled || self.swapping_out.is_some() {
+            return;
+        }
+        let mut candidates = resource_manager
+            .blocked_processes_mut()
+            .filter(|process| {
+                (process.software_context.priority == 0) == (priority == 0)
+                    && memory_manager.resident_blocks(&process.address_space) > 0
+            })
+            .collect::<Vec<_>>();
+        let reclaimable = candidates
+            .iter()
+            .map(|process| memory_manager.resident_blocks(&process.address_space))
+            .sum::<usize>();
+        if memory_manager.free_blocks(priority) + reclaimable
+            < memory_manager.required_blocks(blocks)
+        {
+            return;
+        }
+        let Some(victim) = candidates
+            .iter_mut()
+            .min_by_key(|process| Reverse(memory_manager.resident_blocks(&process.address_space)))
+        else {
+            return;
+        };
+        let swapped = AddressSpace::Swapped(memory_manager.request(&victim.address_space));
+        self.swapping_out = Some(SwapOut {
+            pid: victim.software_context.id,
+            priority: victim.software_context.priority,
+            address_space: std::mem::replace(&mut victim.address_space, swapped),
+            done_at: timestamp + self.config.swap_out_ticks,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::MemoryConfig;
+    use crate::memory::MemoryRequest;
+    use crate::resources::Resource;
+    use crate::structures::segment_list::Segment;
+
+    use super::*;
+
+    fn create_swapper() -> Swapper {
+        Swapper::new(&SwapConfig {
+            enabled: true,
+            swap_out_ticks: 2,
+            swap_in_ticks: 1,
+        })
+    }
+
+    fn create_process(id: usize, address_space: AddressSpace) -> Process {
+        Process::new(
+            id,
+            1,
+            1,
+            false,
+            true,
+            false,
+            false,
+            vec![],
+            vec![],
+            vec![],
+            address_space,
+        )
+    }
+
+    /// A user partition of 100 blocks with two processes of 40 blocks, both
+    /// blocked waiting for the only scanner.
+    fn create_blocked_processes() -> (MemoryManager, ResourceManager) {
+        let mut memory_manager = MemoryManager::new(&MemoryConfig {
+            user_partition_size: 100,
+            ..MemoryConfig::default()
+        });
+        let mut resource_manager = ResourceManager::default();
+        let holder = create_process(0, memory_manager.alloc(1, 10).unwrap());
+        resource_manager.request(holder, Resource::Scanner, &mut vec![]);
+        for id in 1..=2 {
+            let process = create_process(id, memory_manager.alloc(1, 40).unwrap());
+            resource_manager.request(process, Resource::Scanner, &mut vec![]);
+        }
+        (memory_manager, resource_manager)
+    }
+
+    mod swap_out {
+        use super::*;
+
+        #[test]
+        fn frees_the_victim_memory_after_the_swap_out_cost() {
+            let (mut memory_manager, mut resource_manager) = create_blocked_processes();
+            let mut swapper = create_swapper();
+            swapper.swap_out(Some((1, 30)), 0, &memory_manager, &mut resource_manager);
+            let mut events = vec![];
+            swapper.on_tick(1, &mut memory_manager, &mut events);
+            assert!(events.is_empty());
+            assert_eq!(memory_manager.free_blocks(1), 10);
+            swapper.on_tick(2, &mut memory_manager, &mut events);
+            assert_eq!(
+                events,
+                vec![SimEvent::SwappedOut {
+                    pid: 1,
+                    priority: 1,
+                    blocks: 40
+                }]
+            );
+            assert_eq!(memory_manager.free_blocks(1), 50);
+            let swapped = resource_manager
+                .blocked_processes_mut()
+                .filter(|process| {
+                    process.address_space == AddressSpace::Swapped(MemoryRequest::Blocks(40))
+                })
+                .count();
+            assert_eq!(swapped, 1);
+        }
+
+        #[test]
+        fn skips_requests_that_would_not_fit_anyway() {
+            let (mut memory_manager, mut resource_manager) = create_blocked_processes();
+            let mut swapper = create_swapper();
+            swapper.swap_out(Some((1, 95)), 0, &memory_manager, &mut resource_manager);
+            swapper.on_tick(2, &mut memory_manager, &mut vec![]);
+            assert_eq!(memory_manager.free_blocks(1), 10);
+        }
+
+        #[test]
+        fn does_nothing_when_disabled() {
+            let (mut memory_manager, mut resource_manager) = create_blocked_processes();
+            let mut swapper = Swapper::new(&SwapConfig::default());
+            swapper.swap_out(Some((1, 30)), 0, &memory_manager, &mut resource_manager);
+            swapper.on_tick(2, &mut memory_manager, &mut vec![]);
+            assert_eq!(memory_manager.free_blocks(1), 10);
+        }
+    }
+
+    mod swap_in {
+        use super::*;
+
+        #[test]
+        fn returns_processes_still_in_memory() {
+            let mut swapper = create_swapper();
+            let process = create_process(
+                0,
+                AddressSpace::Contiguous(Segment {
+                    offset: 64,
+                    length: 10,
+                }),
+            );
+            assert!(swapper.swap_in(process).is_some());
+            assert!(!swapper.has_processes());
+        }
+
+        #[test]
+        fn waits_for_memory_then_the_swap_in_cost() {
+            let mut memory_manager = MemoryManager::new(&MemoryConfig {
+                user_partition_size: 100,
+                ..MemoryConfig::default()
+            });
+            let other = memory_manager.alloc(1, 80).unwrap();
+            let mut swapper = create_swapper();
+            let process = create_process(0, AddressSpace::Swapped(MemoryRequest::Blocks(40)));
+            assert!(swapper.swap_in(process).is_none());
+            let mut events = vec![];
+            assert!(swapper
+                .on_tick(0, &mut memory_manager, &mut events)
+                .is_empty());
+            memory_manager.free(other);
+            assert!(swapper
+                .on_tick(1, &mut memory_manager, &mut events)
+                .is_empty());
+            let ready = swapper.on_tick(2, &mut memory_manager, &mut events);
+            assert_eq!(ready.len(), 1);
+            assert_eq!(
+                events,
+                vec![SimEvent::SwappedIn {
+                    pid: 0,
+                    priority: 1,
+                    address_space: ready[0].address_space.clone(),
+                }]
+            );
+            assert!(!swapper.has_processes());
+        }
+    }
+}
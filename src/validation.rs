@@ -0,0 +1,497 @@
+use std::fmt;
+
+use crate::config::Config;
+use crate::memory::{MemoryBackend, MemoryManager};
+use crate::parsers::{
+    files_parser::DiskOperationDefinition,
+    processes_parser::{MemoryOperationDefinition, ProcessDefinition},
+};
+use crate::resources::Resource;
+use crate::segmentation::SegmentKind;
+use crate::structures::segment_list::Segment;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Severity {
+    /// The run can go on, but the input probably does not mean what it says.
+    Warning,
+    /// The run would panic or never finish.
+    Error,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Warning => write!(f, "warning: {}", self.message),
+            Severity::Error => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+/// Checks a parsed workload against itself and the configuration before it
+/// is simulated.
+pub fn validate(
+    config: &Config,
+    processes: &[ProcessDefinition],
+    num_blocks: usize,
+    alloc_disk_blocks: &[(char, Segment)],
+    disk_operations: &[DiskOperationDefinition],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for process in processes {
+        validate_process(config, process, &mut diagnostics);
+    }
+    validate_disk(num_blocks, alloc_disk_blocks, &mut diagnostics);
+    for operation in disk_operations {
+        let (process_id, file_name) = match operation {
+            DiskOperationDefinition::Create {
+                process_id,
+                file_name,
+                ..
+            } => (*process_id, *file_name),
+            DiskOperationDefinition::Erase {
+                process_id,
+                file_name,
+            } => (*process_id, *file_name),
+        };
+        if process_id >= processes.len() {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                message: format!(
+                    "the disk operation on file {} refers to process {}, which does not exist",
+                    file_name, process_id
+                ),
+            });
+        }
+    }
+    diagnostics
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+fn validate_process(
+    config: &Config,
+    process: &ProcessDefinition,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut report = |severity, message: String| {
+        diagnostics.push(Diagnostic {
+            severity,
+            message: format!("process {}: {}", process.id, message),
+        })
+    };
+    let user_queues = config.scheduler.user_queues;
+    if process.priority > user_queues {
+        report(
+            Severity::Error,
+            format!(
+                "priority {} is above the lowest user queue ({})",
+                process.priority, user_queues
+            ),
+        );
+    }
+    // `request:` operations for a device, which may also be flagged.
+    let requests = |resource: Resource| {
+        process
+            .resource_requests
+            .iter()
+            .filter(|request| request.resource == resource)
+            .count()
+    };
+    let resources = [
+        (
+            process.use_scanner,
+            Resource::Scanner,
+            config.resources.scanners,
+        ),
+        (
+            process.use_printer,
+            Resource::Printer,
+            config.resources.printers,
+        ),
+        (process.use_modem, Resource::Modem, config.resources.modems),
+        (
+            process.use_sata,
+            Resource::SataDevice,
+            config.resources.sata_devices,
+        ),
+    ];
+    for (flag, resource, units) in resources {
+        if !flag && requests(resource) == 0 {
+            continue;
+        }
+        if process.priority == 0 && requests(resource) > 0 {
+            report(
+                Severity::Error,
+                format!(
+                    "real-time processes cannot use I/O devices, found `request:{}`",
+                    resource.name()
+                ),
+            );
+        } else if process.priority == 0 {
+            report(
+                Severity::Warning,
+                format!(
+                    "real-time processes cannot use I/O devices, the {} request is ignored",
+                    resource.name()
+                ),
+            );
+        } else if units == 0 {
+            report(
+                Severity::Error,
+                format!("requests a {} but none is configured", resource.name()),
+            );
+        }
+    }
+    for (resource, claim) in &process.max_claims {
+        let (flag, _, units) = resources[*resource as usize];
+        let requested = usize::from(flag) + requests(*resource);
+        if *claim > units {
+            report(
+                Severity::Error,
+                format!(
+                    "claims {} {} units but only {} are configured",
+                    claim,
+                    resource.name(),
+                    units
+                ),
+            );
+        } else if *claim < requested {
+            report(
+                Severity::Warning,
+                format!(
+                    "claims {} {} units but requests {}, the claim is raised",
+                    claim,
+                    resource.name(),
+                    requested
+                ),
+            );
+        }
+    }
+    let segment_accesses = process
+        .memory_operations
+        .iter()
+        .any(|operation| matches!(operation, MemoryOperationDefinition::SegmentAccess { .. }));
+    if config.memory.backend == MemoryBackend::Paged
+        && (process.segments.is_some() || segment_accesses)
+    {
+        report(
+            Severity::Warning,
+            String::from(
+                "segments are paged as a whole with paging, segment accesses only count as CPU ticks",
+            ),
+        );
+    }
+    let resizes = process.memory_operations.iter().any(|operation| {
+        matches!(
+            operation,
+            MemoryOperationDefinition::Grow { .. } | MemoryOperationDefinition::Shrink { .. }
+        )
+    });
+    if config.memory.backend == MemoryBackend::Paged && resizes {
+        report(
+            Severity::Warning,
+            String::from("paged memory cannot grow or shrink, the requests are ignored"),
+        );
+    }
+    let pages = process.num_memory_blocks.div_ceil(config.memory.page_size);
+    for operation in &process.memory_operations {
+        match operation {
+            MemoryOperationDefinition::Access { .. }
+                if config.memory.backend != MemoryBackend::Paged =>
+            {
+                report(
+                    Severity::Warning,
+                    String::from("page references only count as CPU ticks without paging"),
+                );
+                break;
+            }
+            MemoryOperationDefinition::Access { page } if *page >= pages => report(
+                Severity::Warning,
+                format!(
+                    "page {} is outside its {} pages, the reference only counts as a CPU tick",
+                    page, pages
+                ),
+            ),
+            MemoryOperationDefinition::Access { .. }
+            | MemoryOperationDefinition::SegmentAccess { .. }
+            | MemoryOperationDefinition::Grow { .. }
+            | MemoryOperationDefinition::Shrink { .. } => {}
+        }
+    }
+    let partition = match process.priority {
+        0 => "real-time",
+        _ => "user",
+    };
+    let max_request = MemoryManager::new(&config.memory).max_request(process.priority);
+    // Segments are placed one by one, except with paging.
+    let largest_request = match process.segments {
+        Some(segments) if config.memory.backend != MemoryBackend::Paged => SegmentKind::ALL
+            .into_iter()
+            .map(|kind| segments.size(kind))
+            .max()
+            .unwrap_or(0),
+        _ => process.num_memory_blocks,
+    };
+    if largest_request > max_request {
+        report(
+            Severity::Warning,
+            format!(
+                "{} memory blocks do not fit in the {} partition, which gives at most {}, it will never run",
+                largest_request, partition, max_request
+            ),
+        );
+    }
+}
+
+fn validate_disk(
+    num_blocks: usize,
+    alloc_disk_blocks: &[(char, Segment)],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut files = alloc_disk_blocks.to_vec();
+    files.sort_by_key(|(_, segment)| segment.offset);
+    for (file_name, segment) in &files {
+        if segment.offset + segment.length > num_blocks {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!(
+                    "file {} occupies blocks {}..{}, beyond the {} blocks of the disk",
+                    file_name,
+                    segment.offset,
+                    segment.offset + segment.length,
+                    num_blocks
+                ),
+            });
+        }
+    }
+    for pair in files.windows(2) {
+        let ((first_name, first), (second_name, second)) = (&pair[0], &pair[1]);
+        if first.offset + first.length > second.offset {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!(
+                    "files {} and {} overlap at block {}",
+                    first_name, second_name, second.offset
+                ),
+            });
+        }
+    }
+    for (index, (file_name, _)) in alloc_disk_blocks.iter().enumerate() {
+        if alloc_disk_blocks[..index]
+            .iter()
+            .any(|(other, _)| other == file_name)
+        {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                message: format!("file {} is allocated more than once", file_name),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::processes_parser::ResourceRequestDefinition;
+    use crate::segmentation::SegmentSizes;
+
+    use super::*;
+
+    fn create_process_definition(id: usize, priority: usize, blocks: usize) -> ProcessDefinition {
+        ProcessDefinition {
+            id,
+            init_time: 0,
+            priority,
+            cpu_time: 1,
+            num_memory_blocks: blocks,
+            segments: None,
+            use_printer: false,
+            use_scanner: false,
+            use_modem: false,
+            use_sata: false,
+            memory_operations: vec![],
+            resource_requests: vec![],
+            max_claims: vec![],
+        }
+    }
+
+    fn severities(diagnostics: &[Diagnostic]) -> Vec<Severity> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.severity)
+            .collect()
+    }
+
+    mod validate {
+        use super::*;
+
+        #[test]
+        fn accepts_a_valid_workload() {
+            let processes = vec![create_process_definition(0, 1, 64)];
+            let files = vec![(
+                'X',
+                Segment {
+                    offset: 0,
+                    length: 2,
+                },
+            )];
+            let operations = vec![DiskOperationDefinition::Erase {
+                process_id: 0,
+                file_name: 'X',
+            }];
+            let diagnostics = validate(&Config::default(), &processes, 4, &files, &operations);
+            assert_eq!(diagnostics, vec![]);
+        }
+
+        #[test]
+        fn reports_process_problems() {
+            let mut real_time = create_process_definition(0, 0, 65);
+            real_time.use_printer = true;
+            let processes = vec![real_time, create_process_definition(1, 4, 1)];
+            let diagnostics = validate(&Config::default(), &processes, 0, &[], &[]);
+            assert_eq!(
+                severities(&diagnostics),
+                vec![Severity::Warning, Severity::Warning, Severity::Error]
+            );
+            assert!(has_errors(&diagnostics));
+        }
+
+        #[test]
+        fn rejects_resource_requests_from_real_time_processes() {
+            let mut real_time = create_process_definition(0, 0, 1);
+            real_time.resource_requests = vec![ResourceRequestDefinition {
+                resource: Resource::Modem,
+                after: 0,
+            }];
+            let diagnostics = validate(&Config::default(), &[real_time], 0, &[], &[]);
+            assert_eq!(
+                diagnostics[0].message,
+                "process 0: real-time processes cannot use I/O devices, found `request:modem`"
+            );
+            assert!(has_errors(&diagnostics));
+        }
+
+        #[test]
+        fn reports_pages_outside_the_address_space() {
+            let mut process = create_process_definition(0, 1, 20);
+            process.memory_operations = vec![
+                MemoryOperationDefinition::Access { page: 1 },
+                MemoryOperationDefinition::Access { page: 2 },
+            ];
+            let mut config = Config::default();
+            let diagnostics = validate(&config, std::slice::from_ref(&process), 0, &[], &[]);
+            assert_eq!(severities(&diagnostics), vec![Severity::Warning]);
+            config.memory.backend = MemoryBackend::Paged;
+            let diagnostics = validate(&config, &[process], 0, &[], &[]);
+            assert_eq!(
+                diagnostics[0].message,
+                "process 0: page 2 is outside its 2 pages, the reference only counts as a CPU tick"
+            );
+        }
+
+        #[test]
+        fn places_segments_one_by_one_unless_paged() {
+            let mut process = create_process_definition(0, 1, 1000);
+            process.segments = Some(SegmentSizes {
+                code: 520,
+                data: 400,
+                stack: 80,
+            });
+            let mut config = Config::default();
+            config.memory.user_partition_size = 1000;
+            let diagnostics = validate(&config, std::slice::from_ref(&process), 0, &[], &[]);
+            assert_eq!(diagnostics, vec![]);
+            config.memory.backend = MemoryBackend::Buddy;
+            let diagnostics = validate(&config, std::slice::from_ref(&process), 0, &[], &[]);
+            assert_eq!(severities(&diagnostics), vec![Severity::Warning]);
+            config.memory.backend = MemoryBackend::Paged;
+            let diagnostics = validate(&config, &[process], 0, &[], &[]);
+            assert_eq!(
+                diagnostics[0].message,
+                "process 0: segments are paged as a whole with paging, segment accesses only count as CPU ticks"
+            );
+        }
+
+        #[test]
+        fn checks_max_claims_against_requests_and_units() {
+            let mut process = create_process_definition(0, 1, 1);
+            process.use_printer = true;
+            process.resource_requests = vec![ResourceRequestDefinition {
+                resource: Resource::Printer,
+                after: 0,
+            }];
+            process.max_claims = vec![(Resource::Printer, 1), (Resource::Modem, 2)];
+            let diagnostics = validate(&Config::default(), &[process], 0, &[], &[]);
+            assert_eq!(
+                diagnostics
+                    .iter()
+                    .map(|diagnostic| diagnostic.message.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "process 0: claims 1 printer units but requests 2, the claim is raised",
+                    "process 0: claims 2 modem units but only 1 are configured",
+                ]
+            );
+        }
+
+        #[test]
+        fn reports_unknown_process_ids() {
+            let operations = vec![DiskOperationDefinition::Create {
+                process_id: 3,
+                file_name: 'A',
+                file_size: 1,
+            }];
+            let diagnostics = validate(&Config::default(), &[], 0, &[], &operations);
+            assert_eq!(severities(&diagnostics), vec![Severity::Warning]);
+            assert!(!has_errors(&diagnostics));
+        }
+
+        #[test]
+        fn reports_overlapping_and_out_of_disk_files() {
+            let files = vec![
+                (
+                    'X',
+                    Segment {
+                        offset: 0,
+                        length: 3,
+                    },
+                ),
+                (
+                    'Y',
+                    Segment {
+                        offset: 2,
+                        length: 1,
+                    },
+                ),
+                (
+                    'X',
+                    Segment {
+                        offset: 4,
+                        length: 2,
+                    },
+                ),
+            ];
+            let diagnostics = validate(&Config::default(), &[], 5, &files, &[]);
+            assert_eq!(
+                diagnostics
+                    .iter()
+                    .map(|diagnostic| diagnostic.message.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    "file X occupies blocks 4..6, beyond the 5 blocks of the disk",
+                    "files X and Y overlap at block 2",
+                    "file X is allocated more than once",
+                ]
+            );
+        }
+    }
+}